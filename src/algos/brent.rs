@@ -0,0 +1,277 @@
+use std::{error::Error, fmt::{self, Display}};
+use crate::{expr::Expr, errors::{DomainRequiredError, NoSignChangeError}};
+use super::{Report, Settings, Solution, Variable};
+
+/// The number of intervals a declared domain is split into when searching it for a sign change.
+const SCAN_INTERVALS: usize = 100;
+
+/// The number of intervals a declared domain is split into when searching it for every root.
+const ROOT_SCAN_INTERVALS: usize = 1000;
+
+/// The largest distance from the guess that is searched for a sign change when no domain is declared.
+const MAX_REACH: f64 = 1E20;
+
+/// Returns `true` if a continuous function must have a root between two of its (finite) values.
+fn straddles(fa: f64, fb: f64) -> bool {
+    fa.is_finite() && fb.is_finite() && (fa == 0.0 || fb == 0.0 || fa.signum() != fb.signum())
+}
+
+/// Splits `[lo, hi]` into `n` intervals and returns every interval that `f` changes sign on.
+fn scan(f: &dyn Fn(f64) -> f64, lo: f64, hi: f64, n: usize) -> Vec<(f64, f64)> {
+    let width = (hi - lo) / n as f64;
+    let mut res = vec![];
+
+    let mut a = lo;
+    let mut fa = f(a);
+    for i in 1..=n {
+        let b = if i == n { hi } else { lo + width * i as f64 };
+        let fb = f(b);
+        if straddles(fa, fb) {
+            res.push((a, b));
+        }
+        if fb.is_finite() || !fa.is_finite() {
+            a = b; // skip over points where `f` is undefined
+            fa = fb;
+        }
+    }
+    res
+}
+
+/// Finds an interval that `f` changes sign on by stepping outward from `x0`
+/// in both directions with steps that double in size.
+fn expand(f: &dyn Fn(f64) -> f64, x0: f64) -> Option<(f64, f64)> {
+    let mut h = 0.01 * x0.abs().max(1.0);
+    let (mut left, mut right) = ((x0, f(x0)), (x0, f(x0)));
+
+    while h < MAX_REACH {
+        let l = (x0 - h, f(x0 - h));
+        let r = (x0 + h, f(x0 + h));
+
+        if straddles(l.1, left.1) {
+            return Some((l.0, left.0))
+        } else if straddles(right.1, r.1) {
+            return Some((right.0, r.0))
+        }
+
+        // skip over points where `f` is undefined
+        if l.1.is_finite() || !left.1.is_finite() { left = l; }
+        if r.1.is_finite() || !right.1.is_finite() { right = r; }
+
+        h *= 2.0;
+    }
+    None
+}
+
+/// Narrows an interval that `f` changes sign on with Brent's method, returning the 
+/// best estimate of the root, the other end of the narrowed interval, and the number 
+/// of iterations taken. 
+fn refine(f: &dyn Fn(f64) -> f64, mut a: f64, mut b: f64, settings: &Settings) -> (f64, f64, usize) {
+    let (mut fa, mut fb) = (f(a), f(b));
+    if fa.abs() < fb.abs() {
+        (a, b, fa, fb) = (b, a, fb, fa);
+    }
+
+    let (mut c, mut fc) = (a, fa);
+    let mut d = c;
+    let mut bisected = true;
+    let mut count: usize = 0;
+
+    while fb.abs() > settings.tolerance {
+        let delta = 2.0 * f64::EPSILON * b.abs().max(1.0);
+        if (b - a).abs() <= delta || count >= settings.max_iterations {
+            break // the interval can't be narrowed further without reaching the tolerance
+        }
+        count += 1;
+
+        let mut s = if fa != fc && fb != fc { // inverse quadratic interpolation
+            a * fb * fc / ((fa - fb) * (fa - fc)) +
+            b * fa * fc / ((fb - fa) * (fb - fc)) +
+            c * fa * fb / ((fc - fa) * (fc - fb))
+        } else { // secant method
+            b - fb * (b - a) / (fb - fa)
+        };
+
+        let lo = (3.0 * a + b) / 4.0;
+        let outside = !((lo < s && s < b) || (b < s && s < lo));
+        let slow = if bisected {
+            (s - b).abs() >= (b - c).abs() / 2.0 || (b - c).abs() < delta
+        } else {
+            (s - b).abs() >= (c - d).abs() / 2.0 || (c - d).abs() < delta
+        };
+
+        bisected = outside || slow || !s.is_finite();
+        if bisected {
+            s = (a + b) / 2.0;
+        }
+
+        let fs = f(s);
+        d = c;
+        (c, fc) = (b, fb);
+
+        if straddles(fa, fs) {
+            (b, fb) = (s, fs);
+        } else {
+            (a, fa) = (s, fs);
+        }
+
+        if fa.abs() < fb.abs() {
+            (a, b, fa, fb) = (b, a, fb, fa);
+        }
+    }
+
+    (b, a, count)
+}
+
+/// Solves a single equation for a single unknown value via Brent's method.
+///
+/// A sign change is first found in the unknown's domain, or by searching outward
+/// from the guess value if no domain is given. Brent's method then narrows the
+/// interval with a mix of bisection, secant, and inverse quadratic interpolation
+/// steps, so it can't diverge. A `NoSignChangeError` is returned if the equation
+/// does not change sign anywhere that was searched.
+///
+/// The solution is reported as converged only if the residual reaches `settings.tolerance`.
+/// Discontinuities that change sign (e.g. `1/x` at 0) are found but are not converged.
+///
+/// # Example
+/// ```
+/// use nexsys::algos::{Variable, Settings};
+/// use nexsys::algos::brent;
+/// use nexsys::expr::Expr;
+///
+/// let my_eqn = Expr::parse("x^3 - 2*x - 5").unwrap();
+/// let my_guess = ("x", Variable::new(-10.0, None));
+///
+/// let root = brent(&my_eqn, my_guess, &Settings::default()).unwrap();
+///
+/// assert!(root.converged());
+/// assert_eq!((root.unwrap().1.as_f64() * 1000.0).round(), 2095.0)
+/// ```
+pub fn brent<'a>(equation: &Expr, guess: (&'a str, Variable), settings: &Settings)
+-> Result<Report<(&'a str, Variable)>, Box<dyn Error>> {
+
+    let f = |x: f64| equation
+        .eval(&|v| if v == guess.0 { Some(x) } else { None })
+        .unwrap_or(f64::NAN);
+
+    let mut xi = guess.1;
+    let x0 = xi.as_f64();
+
+    let found = match xi.get_domain() {
+        Some([lo, hi]) => scan(&f, lo, hi, SCAN_INTERVALS)
+            .into_iter()
+            .min_by(|p, q| ((p.0 + p.1) / 2.0 - x0).abs().total_cmp(&((q.0 + q.1) / 2.0 - x0).abs())),
+        None => expand(&f, x0)
+    };
+
+    let (a, b) = match found {
+        Some(bracket) => bracket,
+        None => return Err(Box::new(NoSignChangeError(equation.to_string())))
+    };
+
+    let (b, _, count) = refine(&f, a, b, settings);
+
+    xi.change(b);
+    let residual = f(xi.as_f64()).abs();
+
+    if residual <= settings.tolerance {
+        Ok(Report { solution: Solution::Converged((guess.0, xi)), iterations: count, residual, undetermined: vec![], criteria: None, history: vec![] })
+    } else {
+        Ok(Report { solution: Solution::NonConverged((guess.0, xi)), iterations: count, residual, undetermined: vec![], criteria: None, history: vec![] })
+    }
+}
+
+/// Finds every root of a single equation on the domain of its unknown. The domain
+/// is split into small intervals, and each interval that the equation changes sign 
+/// on is narrowed down with Brent's method. The roots are given in ascending order.
+/// 
+/// Roots where the equation touches zero without changing sign (e.g. `x^2` at 0) 
+/// can't be found this way, and sign changes at discontinuities are ignored. A sign 
+/// change is a root if the residual reaches `settings.tolerance`, or if the equation 
+/// shrinks toward zero as its interval is narrowed (a badly scaled equation may not 
+/// reach the tolerance even at the closest floating point values to its root). 
+/// A `DomainRequiredError` is returned if the unknown has no domain.
+/// # Example
+/// ```
+/// use nexsys::algos::{Variable, Settings};
+/// use nexsys::algos::all_roots;
+/// use nexsys::expr::Expr;
+///
+/// let my_eqn = Expr::parse("x^3 - 6*x^2 + 11*x - 6").unwrap();
+/// let my_guess = ("x", Variable::new(0.0, Some([-10.0, 10.0])));
+///
+/// let roots = all_roots(&my_eqn, my_guess, &Settings::default()).unwrap().unwrap();
+///
+/// assert_eq!(roots.iter().map(|i| i.round()).collect::<Vec<f64>>(), vec![1.0, 2.0, 3.0]);
+/// ```
+pub fn all_roots(equation: &Expr, guess: (&str, Variable), settings: &Settings)
+-> Result<Report<Vec<f64>>, Box<dyn Error>> {
+
+    let f = |x: f64| equation
+        .eval(&|v| if v == guess.0 { Some(x) } else { None })
+        .unwrap_or(f64::NAN);
+
+    let [lo, hi] = match guess.1.get_domain() {
+        Some(d) => d,
+        None => return Err(Box::new(DomainRequiredError(guess.0.to_string())))
+    };
+
+    let mut roots: Vec<f64> = vec![];
+    let mut iterations = 0;
+    let mut residual: f64 = 0.0;
+
+    for (a, b) in scan(&f, lo, hi, ROOT_SCAN_INTERVALS) {
+        let (x, other, count) = refine(&f, a, b, settings);
+        iterations += count;
+
+        // near a root, the equation shrinks along with the interval, while across 
+        // a discontinuity it stays (at least) as large as the jump
+        let fx = f(x).abs();
+        if fx > settings.tolerance && 2.0 * fx.max(f(other).abs()) >= f(a).abs().min(f(b).abs()) {
+            continue // a discontinuity, not a root
+        }
+
+        // a root on the edge of two intervals is found twice
+        if roots.last().is_none_or(|r| (x - r).abs() > 4.0 * f64::EPSILON * x.abs().max(1.0)) {
+            roots.push(x);
+            residual = residual.max(fx);
+        }
+    }
+
+    Ok(Report { solution: Solution::Converged(roots), iterations, residual, undetermined: vec![], criteria: None, history: vec![] })
+}
+
+/// Rules for picking one of several roots of an equation.
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum RootChoice {
+    /// The smallest root.
+    Smallest,
+    /// The largest root.
+    Largest,
+    /// The root nearest to the given value.
+    Nearest(f64)
+}
+impl RootChoice {
+    /// Picks a root from a list of roots sorted in ascending order, 
+    /// returning `None` if the list is empty.
+    pub fn select(&self, roots: &[f64]) -> Option<f64> {
+        match self {
+            RootChoice::Smallest => roots.first().copied(),
+            RootChoice::Largest => roots.last().copied(),
+            RootChoice::Nearest(x) => roots.iter()
+                .copied()
+                .min_by(|a, b| (a - x).abs().total_cmp(&(b - x).abs()))
+        }
+    }
+}
+impl Display for RootChoice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RootChoice::Smallest    => write!(f, "smallest"),
+            RootChoice::Largest     => write!(f, "largest"),
+            RootChoice::Nearest(x)  => write!(f, "nearest {x}")
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::{collections::HashMap, error::Error};
+use crate::expr::Expr;
+use super::{Method, Report, Settings, Solution, Variable, JacobianUpdate, backtrack, cap_step, project_step, jacobian_by, residuals, take_step};
+
+/// The variables in the order of a Jacobian's columns, and the rows of its inverse.
+type Inverse = (Vec<String>, Vec<Vec<f64>>);
+
+/// Returns the variables in the order of the Jacobian's columns along with the 
+/// inverse of the Jacobian (as rows) of a system at `guess`.
+fn inverse_jacobian(system: &[Expr], guess: &HashMap<&str, Variable>, settings: &Settings) 
+-> Result<Inverse, Box<dyn Error>> {
+    let j = jacobian_by(system, guess, settings.derivative)?;
+    let lu = j.lu()?;
+    let n = j.size;
+
+    let mut h = vec![vec![0.0; n]; n];
+    for c in 0..n {
+        let mut e = vec![0.0; n];
+        e[c] = 1.0;
+        for (r, v) in lu.solve(&e)?.into_iter().enumerate() {
+            h[r][c] = v;
+        }
+    }
+
+    Ok((j.vars.unwrap(), h)) // jacobians always name their columns
+}
+
+/// Returns the dot product of two equal-length slices.
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(i, j)| i * j).sum()
+}
+
+/// Attempts to solve the equations passed to `system` via Broyden's method.
+/// 
+/// Instead of re-evaluating the Jacobian every iteration like `mv_newton_raphson`, 
+/// the inverse of the Jacobian is found once and then corrected with a rank-one 
+/// update after each step, using either Broyden's "good" or "bad" update as given
+/// by `settings.update`. Each iteration costs one evaluation of the system instead of
+/// the n evaluations needed for a new Jacobian, which pays off when the system is large
+/// or its equations are expensive. The Jacobian is evaluated again whenever a step 
+/// fails to reduce the residual by at least 10%.
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use nexsys::algos::{Variable, Settings, JacobianUpdate};
+/// use nexsys::algos::broyden;
+/// use nexsys::expr::Expr;
+/// 
+/// let my_sys = vec![
+///     Expr::parse("x^2 + y - 11").unwrap(), 
+///     Expr::parse("x + y^2 - 7").unwrap()
+/// ];
+/// let guess = HashMap::from([
+///     ("x", Variable::new(2.5, None)),
+///     ("y", Variable::new(2.5, None))
+/// ]);
+/// let settings = Settings { update: JacobianUpdate::GoodBroyden, ..Settings::default() };
+/// 
+/// let ans = broyden(&my_sys, guess, &settings).unwrap().unwrap();
+/// 
+/// assert_eq!(ans["x"].as_f64().round(), 3.0);
+/// assert_eq!(ans["y"].as_f64().round(), 2.0);
+/// ```
+pub fn broyden<'a>(system: &[Expr], mut guess: HashMap<&'a str, Variable>, settings: &Settings) 
+-> Result<Report<HashMap<&'a str, Variable>>, Box<dyn Error>> {
+
+    let error = |fx: &[f64]| fx.iter().map(|i| i.abs()).sum::<f64>();
+    let merit = |fx: &[f64]| 0.5 * fx.iter().map(|i| i * i).sum::<f64>();
+
+    let f0 = residuals(system, &guess)?;
+    let mut fx = f0.clone();
+    let mut criteria = settings.convergence.measure(&fx, &f0, &f0, &HashMap::new(), &[], &HashMap::new());
+    let (mut vars, mut h) = inverse_jacobian(system, &guess, settings)?;
+    let mut count: usize = 0;
+
+    while !settings.convergence.met(&criteria, settings.tolerance) {
+
+        count += 1;
+        if count > settings.max_iterations {
+            return Ok(Report { solution: Solution::NonConverged(guess), iterations: count, residual: error(&fx), undetermined: vec![], criteria: Some(criteria), history: vec![] })
+        }
+        settings.monitor.iteration(Method::Broyden, count, error(&fx))?;
+
+        let mut step: HashMap<String, f64> = vars.iter()
+            .cloned()
+            .zip(h.iter().map(|row| -dot(row, &fx)))
+            .collect();
+        let full = step.clone();
+        cap_step(&mut step, settings);
+        project_step(&mut step, &guess);
+
+        let lambda = if settings.line_search {
+            backtrack(merit(&fx), |lambda| Ok(merit(&residuals(system, &take_step(&guess, &step, lambda))?)))?
+        } else {
+            1.0
+        };
+
+        let next = take_step(&guess, &step, lambda);
+        let f_next = residuals(system, &next)?;
+
+        // the step actually taken (after domains are applied) and the resulting change in the residuals
+        let s: Vec<f64> = vars.iter().map(|v| next[v.as_str()].as_f64() - guess[v.as_str()].as_f64()).collect();
+        let y: Vec<f64> = f_next.iter().zip(&fx).map(|(i, j)| i - j).collect();
+
+        let stalled = error(&f_next) > 0.9 * error(&fx);
+        criteria = settings.convergence.measure(&f_next, &fx, &f0, &full, &[], &HashMap::new());
+        guess = next;
+        fx = f_next;
+
+        if stalled {
+            (vars, h) = inverse_jacobian(system, &guess, settings)?;
+            continue
+        }
+
+        // rank-one update of the inverse jacobian, `h += (s - hy)vᵀ / vᵀy`
+        let hy: Vec<f64> = h.iter().map(|row| dot(row, &y)).collect();
+        let v: Vec<f64> = match settings.update {
+            JacobianUpdate::BadBroyden => y.clone(),
+            _ => (0..s.len()).map(|c| (0..s.len()).map(|r| s[r] * h[r][c]).sum()).collect() // sᵀh
+        };
+
+        let denom = dot(&v, &y);
+        if denom.abs() < f64::EPSILON || !denom.is_finite() {
+            (vars, h) = inverse_jacobian(system, &guess, settings)?;
+            continue
+        }
+
+        for (r, row) in h.iter_mut().enumerate() {
+            let u = (s[r] - hy[r]) / denom;
+            for (c, val) in row.iter_mut().enumerate() {
+                *val += u * v[c];
+            }
+        }
+    }
+
+    let residual = error(&fx);
+    Ok(Report { solution: Solution::Converged(guess), iterations: count, residual, undetermined: vec![], criteria: Some(criteria), history: vec![] })
+}
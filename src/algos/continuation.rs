@@ -0,0 +1,239 @@
+use std::{collections::HashMap, error::Error, fmt::{self, Display}};
+use crate::{
+    expr::Expr, 
+    errors::{ContinuationError, SolverConvergenceError}, 
+    mvcalc::{jacobian_rows, LU}, 
+    sweep::Table
+};
+use super::{Settings, Variable, residuals};
+
+/// The largest number of Newton iterations made to return to the branch after each step.
+const CORRECTOR_ITERATIONS: usize = 8;
+
+/// The largest number of points traced on a branch, which stops closed branches from being traced forever.
+const MAX_POINTS: usize = 10_000;
+
+/// The smallest step taken along the branch before giving up on it, relative to the nominal step.
+const MIN_STEP: f64 = 1E-4;
+
+/// Special points on a branch of solutions.
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(PartialEq, Eq)]
+pub enum Point {
+    /// The branch turns back on itself (a fold), so the parameter reaches a local extreme.
+    TurningPoint,
+    /// Another branch crosses the traced branch.
+    Bifurcation
+}
+impl Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Point::TurningPoint => write!(f, "turning point"),
+            Point::Bifurcation  => write!(f, "bifurcation")
+        }
+    }
+}
+
+/// A branch of solutions traced by `arc_length`.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct Branch {
+    /// The points on the branch in the order they were traced. The parameter is the first column.
+    pub table: Table,
+    /// The special point found just before each row of the table, if any.
+    pub points: Vec<Option<Point>>
+}
+impl Branch {
+    /// Returns the rows of the table that are flagged with the given kind of point.
+    pub fn find(&self, kind: Point) -> Vec<usize> {
+        self.points.iter()
+            .enumerate()
+            .filter(|(_, p)| **p == Some(kind))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Returns the dot product of two equal-length slices.
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(i, j)| i * j).sum()
+}
+
+/// Returns the vector `y` as values of `vars`.
+fn point<'a>(vars: &[&'a str], y: &[f64]) -> HashMap<&'a str, Variable> {
+    vars.iter().zip(y).map(|(&k, &v)| (k, Variable::new(v, None))).collect()
+}
+
+/// Solves `system` together with the extra equation `row·y = target` via Newton's method, 
+/// starting from `y`. Returns the solution and the number of iterations taken, or `None` 
+/// if the iterations don't converge.
+fn correct(system: &[Expr], vars: &[&str], mut y: Vec<f64>, row: &[f64], target: f64, settings: &Settings) 
+-> Option<(Vec<f64>, usize)> {
+
+    for i in 0..=CORRECTOR_ITERATIONS {
+        let guess = point(vars, &y);
+        let mut r = residuals(system, &guess).ok()?;
+        r.push(dot(row, &y) - target);
+
+        let error: f64 = r.iter().map(|j| j.abs()).sum();
+        if error <= settings.tolerance {
+            return Some((y, i))
+        } else if !error.is_finite() || i == CORRECTOR_ITERATIONS {
+            break
+        }
+
+        let mut a = jacobian_rows(system, &guess, vars).ok()?;
+        a.push(row.to_vec());
+        let dy = LU::factor(a, None).ok()?.solve(&r).ok()?;
+
+        for (j, d) in y.iter_mut().zip(dy) {
+            *j -= d;
+        }
+    }
+    None
+}
+
+/// Returns the unit tangent of the branch at `y`, oriented so that it agrees with `row`,
+/// along with the determinant of the Jacobian of the system augmented with `row`.
+fn tangent(system: &[Expr], vars: &[&str], y: &[f64], row: &[f64]) -> Result<(Vec<f64>, f64), Box<dyn Error>> {
+    let mut a = jacobian_rows(system, &point(vars, y), vars)?;
+    a.push(row.to_vec());
+
+    let names = vars.iter().map(|i| i.to_string()).collect();
+    let lu = LU::factor(a, Some(&names))?;
+
+    let mut rhs = vec![0.0; vars.len()];
+    rhs[vars.len() - 1] = 1.0;
+    let t = lu.solve(&rhs)?;
+
+    let norm = dot(&t, &t).sqrt();
+    Ok((t.iter().map(|i| i / norm).collect(), lu.det()))
+}
+
+/// Traces how the solution of a system changes as `parameter` varies over `range` via 
+/// pseudo-arc-length continuation. 
+/// 
+/// `system` must have one more unknown than it has equations, with the extra unknown 
+/// being `parameter`, otherwise a `ContinuationError` is returned. The system is first 
+/// solved with `parameter` fixed at `range[0]`, starting from `start`. Each following point 
+/// is predicted along the tangent of the branch and corrected onto it with Newton's method,
+/// constraining the distance travelled along the branch (the arc length) rather than the 
+/// parameter. This lets the branch be followed around folds, where the parameter turns 
+/// back and a plain sweep of the parameter fails. Steps of up to `step` are taken, and 
+/// are shrunk whenever the corrector fails.
+/// 
+/// Tracing stops when the parameter leaves `range`, in which case the last point is placed 
+/// exactly on the end of the range, or when the branch can't be followed any further.
+/// Turning points are flagged where the parameter's component of the tangent changes sign, 
+/// and bifurcations where the determinant of the augmented Jacobian changes sign. Domains 
+/// are not applied while tracing.
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use nexsys::algos::{arc_length, Point, Settings, Variable};
+/// use nexsys::expr::Expr;
+/// 
+/// // a fold at p = 1
+/// let my_sys = vec![Expr::parse("x^2 + p - 1").unwrap()];
+/// let start = HashMap::from([
+///     ("x", Variable::new(1.0, None)),
+///     ("p", Variable::new(0.0, None))
+/// ]);
+/// 
+/// let branch = arc_length(&my_sys, start, "p", [0.0, 2.0], 0.1, &Settings::default()).unwrap();
+/// 
+/// // the branch goes around the fold and back to p = 0
+/// let x = branch.table.column("x").unwrap();
+/// assert_eq!(x.last().unwrap().round(), -1.0);
+/// assert_eq!(branch.find(Point::TurningPoint).len(), 1);
+/// ```
+pub fn arc_length(system: &[Expr], start: HashMap<&str, Variable>, parameter: &str, range: [f64; 2], step: f64, settings: &Settings) 
+-> Result<Branch, Box<dyn Error>> {
+
+    if !start.contains_key(parameter) || start.len() != system.len() + 1 {
+        return Err(Box::new(ContinuationError(parameter.to_string())))
+    }
+
+    let mut vars: Vec<&str> = start.keys().copied().filter(|&i| i != parameter).collect();
+    vars.sort();
+    vars.insert(0, parameter);
+
+    let (lo, hi) = (range[0].min(range[1]), range[0].max(range[1]));
+    let mut e0 = vec![0.0; vars.len()];
+    e0[0] = 1.0;
+
+    // the first point has the parameter fixed at the start of the range
+    let mut y: Vec<f64> = vars.iter().map(|i| start[i].as_f64()).collect();
+    y[0] = range[0];
+    let (mut y, _) = match correct(system, &vars, y, &e0, range[0], settings) {
+        Some(ans) => ans,
+        None => return Err(Box::new(SolverConvergenceError::default()))
+    };
+
+    let (mut tau, _) = tangent(system, &vars, &y, &e0)?;
+    if range[1] < range[0] {
+        tau.iter_mut().for_each(|i| *i = -*i);
+    }
+    let (_, mut det) = tangent(system, &vars, &y, &tau)?;
+
+    let mut table = Table::new(vars.iter().map(|i| i.to_string()).collect());
+    let mut points = vec![None];
+    table.rows.push(y.clone());
+
+    let mut ds = step.abs();
+    while table.rows.len() < MAX_POINTS {
+        settings.monitor.check()?;
+
+        let predicted: Vec<f64> = y.iter().zip(&tau).map(|(i, t)| i + ds * t).collect();
+        let target = dot(&tau, &y) + ds;
+
+        let (next, iterations) = match correct(system, &vars, predicted, &tau, target, settings) {
+            Some(ans) => ans,
+            None => {
+                ds /= 2.0;
+                if ds < MIN_STEP * step.abs() {
+                    break
+                }
+                continue
+            }
+        };
+
+        if next[0] < lo || next[0] > hi {
+            // finish on the end of the range, starting from a point interpolated between the last two
+            let end = if next[0] < lo { lo } else { hi };
+            let frac = (end - y[0]) / (next[0] - y[0]);
+            let guess = y.iter().zip(&next).map(|(a, b)| a + frac * (b - a)).collect();
+
+            if let Some((last, _)) = correct(system, &vars, guess, &e0, end, settings) {
+                table.rows.push(last);
+                points.push(None);
+            }
+            break
+        }
+
+        let (t_next, d_next) = tangent(system, &vars, &next, &tau)?;
+
+        points.push(
+            if t_next[0].signum() != tau[0].signum() {
+                Some(Point::TurningPoint)
+            } else if d_next.signum() != det.signum() {
+                Some(Point::Bifurcation)
+            } else {
+                None
+            }
+        );
+        table.rows.push(next.clone());
+
+        y = next;
+        tau = t_next;
+        det = d_next;
+
+        if iterations <= 3 {
+            ds = (1.5 * ds).min(step.abs());
+        }
+    }
+
+    Ok(Branch { table, points })
+}
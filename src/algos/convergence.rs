@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+/// The norm used to combine residuals or steps into a single value.
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Norm {
+    /// The sum of the absolute values.
+    One,
+    /// The Euclidean length.
+    Two,
+    /// The largest absolute value.
+    Infinity
+}
+impl Norm {
+    /// Returns the norm of the given values.
+    pub fn of(&self, x: &[f64]) -> f64 {
+        match self {
+            Norm::One => x.iter().map(|i| i.abs()).sum(),
+            Norm::Two => x.iter().map(|i| i * i).sum::<f64>().sqrt(),
+            Norm::Infinity => x.iter().map(|i| i.abs()).fold(0.0, f64::max)
+        }
+    }
+}
+
+/// The criteria that decide when an iterative method has converged.
+///
+/// A solve converges once its residuals pass, i.e. the norm of the residuals is below
+/// `Settings::tolerance` (or below `relative` times its value at the initial guess), and
+/// every equation with its own tolerance is within it. If step tolerances are given, a
+/// solve also converges once every variable's last full step is within them, as long as
+/// the residuals didn't grow in that step (or are below the square root of the tolerance).
+/// Full steps are measured before they are capped, projected onto domains or shortened by
+/// a line search, so a variable pinned on a bound or a stalled line search can't pass.
+///
+/// When `Settings::scaling` is on, the norms are taken over the scaled residuals and steps.
+/// The tolerances of individual equations and variables are always in their own units.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Convergence {
+    /// The norm used to measure the residuals and steps.
+    pub norm: Norm,
+    /// If given, the residuals also pass once their norm falls below this fraction
+    /// of its value at the initial guess.
+    pub relative: Option<f64>,
+    /// If given, the solve converges once the norm of the last step is below this.
+    pub step: Option<f64>,
+    /// The tolerance on the absolute residual of each equation, in the same order
+    /// as the system. Equations without one are measured by the norm instead.
+    pub equations: Vec<Option<f64>>,
+    /// The tolerance on the absolute step of each variable. Variables without one are
+    /// measured by the norm of the step instead.
+    pub variables: HashMap<String, f64>
+}
+impl Default for Convergence {
+    fn default() -> Convergence {
+        Convergence {
+            norm: Norm::One,
+            relative: None,
+            step: None,
+            equations: vec![],
+            variables: HashMap::new()
+        }
+    }
+}
+impl Convergence {
+    /// Returns the tolerance of the `i`th equation of the system, if it has one.
+    pub fn equation(&self, i: usize) -> Option<f64> {
+        self.equations.get(i).copied().flatten()
+    }
+
+    /// Measures the criteria at an iterate from the residuals there (`fx`), the residuals at
+    /// the previous iterate (`last`) and the initial guess (`f0`), and the last full step, if 
+    /// any. Residuals are divided by `rows` and steps by `cols` before their norms are taken; 
+    /// missing scales are 1.
+    pub(crate) fn measure(
+        &self,
+        fx: &[f64],
+        last: &[f64],
+        f0: &[f64],
+        step: &HashMap<String, f64>,
+        rows: &[f64],
+        cols: &HashMap<String, f64>
+    ) -> Criteria {
+        let scaled = |f: &[f64]| -> Vec<f64> {
+            f.iter().enumerate()
+                .filter(|(i, _)| self.equation(*i).is_none())
+                .map(|(i, f)| f / rows.get(i).unwrap_or(&1.0))
+                .collect()
+        };
+
+        let residual = self.norm.of(&scaled(fx));
+        let initial = self.norm.of(&scaled(f0));
+        let relative = if initial > 0.0 { residual / initial } else { 0.0 };
+        let previous = self.norm.of(&scaled(last));
+        let growth = if previous > 0.0 { residual / previous } else if residual > 0.0 { f64::INFINITY } else { 0.0 };
+
+        let dx: Vec<f64> = step.iter()
+            .filter(|(k, _)| !self.variables.contains_key(*k))
+            .map(|(k, v)| v / cols.get(k).unwrap_or(&1.0))
+            .collect();
+
+        Criteria {
+            residual,
+            relative,
+            growth,
+            step: self.norm.of(&dx),
+            equations: fx.iter().map(|i| i.abs()).collect(),
+            variables: step.iter().map(|(k, v)| (k.to_string(), v.abs())).collect()
+        }
+    }
+
+    /// Returns `true` if the measured criteria satisfy these ones, given the absolute tolerance.
+    pub fn met(&self, criteria: &Criteria, tolerance: f64) -> bool {
+        let equations = criteria.equations.iter()
+            .enumerate()
+            .all(|(i, f)| self.equation(i).is_none_or(|t| *f <= t));
+
+        let residuals = equations && (
+            criteria.residual < tolerance ||
+            self.relative.is_some_and(|r| criteria.relative <= r)
+        );
+
+        // steps only count once one has been taken, and only if every variable has a tolerance
+        let measured = !criteria.variables.is_empty() && (
+            self.step.is_some() ||
+            criteria.variables.keys().all(|k| self.variables.contains_key(k))
+        );
+
+        // small steps only count while they don't come with growing (or large) residuals
+        let settled = criteria.growth <= 1.0 || criteria.residual <= tolerance.sqrt();
+
+        let steps = measured && settled &&
+            self.step.is_none_or(|s| criteria.step <= s) &&
+            criteria.variables.iter().all(|(k, dx)| self.variables.get(k).is_none_or(|t| dx <= t));
+
+        residuals || steps
+    }
+}
+
+/// The values of the convergence criteria that a solve reached at its last iterate.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct Criteria {
+    /// The norm of the residuals of the equations without their own tolerance.
+    pub residual: f64,
+    /// `residual` as a fraction of its value at the initial guess.
+    pub relative: f64,
+    /// `residual` as a fraction of its value at the previous iterate.
+    pub growth: f64,
+    /// The norm of the last full step of the variables without their own tolerance.
+    pub step: f64,
+    /// The absolute residual of each equation.
+    pub equations: Vec<f64>,
+    /// The absolute size of the last full step of each variable, i.e. before it was capped,
+    /// projected onto domains or shortened by a line search. Empty if no step was taken.
+    pub variables: HashMap<String, f64>
+}
@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use crate::{algos::{Equation, Variable}, errors::SolverConstraintError};
+
+/// A group of equations that must be solved simultaneously for an equal number of unknowns.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct Block {
+    /// The unknowns that this block solves for.
+    pub vars: Vec<String>,
+    /// The equations that make up this block.
+    pub eqns: Vec<Equation>,
+    /// The position of each equation in the order they were added to the graph.
+    pub rows: Vec<usize>
+}
+
+/// The bipartite incidence graph of a system's equations and their unknowns.
+///
+/// Unlike `BlockMgr`, which only groups equations with identical sets of unknowns,
+/// `Incidence` matches every equation with an unknown (maximum bipartite matching)
+/// and splits the matched equations into strongly connected components (Tarjan's
+/// algorithm). Each component is a minimal algebraic loop, and the components are
+/// returned in the order that they can be solved.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct Incidence<'a> {
+    eqns: Vec<Equation>,
+    rows: Vec<usize>,
+    added: usize,
+    edges: Vec<Vec<usize>>,
+    vars: Vec<String>,
+    ctx: &'a HashMap<String, Variable>
+}
+impl <'a> Incidence<'a> {
+    /// Initializes a new, empty `Incidence` graph.
+    pub fn new(ctx: &'a HashMap<String, Variable>) -> Incidence<'a> {
+        Incidence { eqns: vec![], rows: vec![], added: 0, edges: vec![], vars: vec![], ctx }
+    }
+
+    /// Adds an equation to the graph, linking it to each of its unknowns.
+    /// Equations without unknowns are ignored.
+    pub fn add_item(&mut self, eqn: &Equation) {
+        let uks = eqn.unknowns(self.ctx);
+        self.added += 1;
+
+        if uks.is_empty() {
+            return;
+        }
+
+        let mut edges = vec![];
+        for uk in uks {
+            let idx = match self.vars.iter().position(|v| *v == uk) {
+                Some(i) => i,
+                None => {
+                    self.vars.push(uk);
+                    self.vars.len() - 1
+                }
+            };
+            edges.push(idx);
+        }
+
+        self.eqns.push(eqn.clone());
+        self.rows.push(self.added - 1);
+        self.edges.push(edges);
+    }
+
+    /// Attempts to find an augmenting path from equation `e`. (Kuhn's algorithm)
+    fn augment(&self, e: usize, seen: &mut [bool], var_match: &mut [Option<usize>]) -> bool {
+        for &v in &self.edges[e] {
+            if seen[v] {
+                continue;
+            }
+            seen[v] = true;
+
+            let free = match var_match[v] {
+                None => true,
+                Some(other) => self.augment(other, seen, var_match)
+            };
+
+            if free {
+                var_match[v] = Some(e);
+                return true
+            }
+        }
+        false
+    }
+
+    /// Returns a maximum matching of the graph as `(eqn_match, var_match)`, where
+    /// `eqn_match[i]` is the unknown assigned to equation `i` and `var_match[j]` is
+    /// the equation assigned to unknown `j`.
+    fn matching(&self) -> (Vec<Option<usize>>, Vec<Option<usize>>) {
+        let mut var_match = vec![None; self.vars.len()];
+
+        for e in 0..self.eqns.len() {
+            let mut seen = vec![false; self.vars.len()];
+            self.augment(e, &mut seen, &mut var_match);
+        }
+
+        let mut eqn_match = vec![None; self.eqns.len()];
+        for (v, e) in var_match.iter().enumerate() {
+            if let Some(e) = e {
+                eqn_match[*e] = Some(v);
+            }
+        }
+
+        (eqn_match, var_match)
+    }
+
+    /// Returns the strongly connected components of the matched equations, in an order
+    /// such that every component comes after the components it depends on.
+    fn components(&self, eqn_match: &[Option<usize>], var_match: &[Option<usize>]) -> Vec<Vec<usize>> {
+        let n = self.eqns.len();
+
+        // equation `i` depends on equation `j` if `i` contains the unknown matched to `j`
+        let deps: Vec<Vec<usize>> = (0..n).map(
+            |i| self.edges[i].iter()
+                .filter(|&&v| eqn_match[i] != Some(v))
+                .filter_map(|&v| var_match[v])
+                .collect()
+        ).collect();
+
+        let mut tarjan = Tarjan {
+            deps: &deps,
+            index: vec![None; n],
+            low: vec![0; n],
+            on_stack: vec![false; n],
+            stack: vec![],
+            count: 0,
+            sccs: vec![]
+        };
+
+        for i in (0..n).filter(|&i| eqn_match[i].is_some()) {
+            if tarjan.index[i].is_none() {
+                tarjan.visit(i);
+            }
+        }
+
+        tarjan.sccs
+    }
+
+    /// Returns the properly constrained blocks of the system in the order that they
+    /// should be solved, or `None` if no such blocks exist.
+    ///
+    /// Blocks that depend on an under-constrained unknown are left out, as are any
+    /// surplus equations that could not be matched with an unknown.
+    pub fn constrained(self) -> Option<Vec<Block>> {
+        let (eqn_match, var_match) = self.matching();
+        let sccs = self.components(&eqn_match, &var_match);
+
+        let mut blocked = vec![false; self.eqns.len()];
+        let mut blocks = vec![];
+
+        for mut scc in sccs {
+            scc.sort();
+
+            // a component can't be solved if it contains an unmatched unknown
+            // or relies on a component that can't be solved.
+            let stuck = scc.iter().any(
+                |&i| self.edges[i].iter().any(
+                    |&v| match var_match[v] {
+                        None => true,
+                        Some(j) => blocked[j]
+                    }
+                )
+            );
+
+            if stuck {
+                for &i in &scc {
+                    blocked[i] = true;
+                }
+                continue;
+            }
+
+            blocks.push(Block {
+                vars: scc.iter().map(|&i| self.vars[eqn_match[i].unwrap()].clone()).collect(),
+                eqns: scc.iter().map(|&i| self.eqns[i].clone()).collect(),
+                rows: scc.iter().map(|&i| self.rows[i]).collect()
+            });
+        }
+
+        if blocks.is_empty() {
+            None
+        } else {
+            Some(blocks)
+        }
+    }
+
+    /// Diagnoses why the equations in the graph can't be solved, reporting the remaining
+    /// unknowns, the over-determined groups of equations, the degrees of freedom, and the 
+    /// variables that should be specified to properly constrain the system.
+    pub fn diagnose(self) -> SolverConstraintError {
+        let (eqn_match, var_match) = self.matching();
+
+        // equations that are reachable by alternating paths from an unmatched equation 
+        // form the over-determined part of the system (Dulmage-Mendelsohn decomposition)
+        let mut seen = vec![false; self.eqns.len()];
+        let mut overdetermined = vec![];
+
+        for start in (0..self.eqns.len()).filter(|&i| eqn_match[i].is_none()) {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+
+            let mut grp = vec![];
+            let mut queue = vec![start];
+
+            while let Some(e) = queue.pop() {
+                grp.push(e);
+                for &v in &self.edges[e] {
+                    if let Some(next) = var_match[v] {
+                        if !seen[next] {
+                            seen[next] = true;
+                            queue.push(next);
+                        }
+                    }
+                }
+            }
+
+            grp.sort();
+            overdetermined.push(
+                grp.iter().map(|&i| self.eqns[i].as_text().trim().to_string()).collect()
+            );
+        }
+
+        let suggestions = (0..self.vars.len())
+            .filter(|&v| var_match[v].is_none())
+            .map(|v| self.vars[v].clone())
+            .collect();
+
+        SolverConstraintError {
+            unsolved: self.vars.clone(),
+            unused: self.eqns.iter().map(|i| i.as_text().trim().to_string()).collect(),
+            overdetermined,
+            degrees_of_freedom: self.vars.len() as isize - self.eqns.len() as isize,
+            suggestions,
+            solution: self.ctx.clone(),
+            log: vec![] // filled in by `Nexsys.solve()`
+        }
+    }
+}
+
+/// State for Tarjan's strongly connected components algorithm.
+struct Tarjan<'a> {
+    deps: &'a [Vec<usize>],
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    count: usize,
+    sccs: Vec<Vec<usize>>
+}
+impl <'a> Tarjan<'a> {
+    fn visit(&mut self, i: usize) {
+        self.index[i] = Some(self.count);
+        self.low[i] = self.count;
+        self.count += 1;
+        self.stack.push(i);
+        self.on_stack[i] = true;
+
+        for &j in &self.deps[i] {
+            match self.index[j] {
+                None => {
+                    self.visit(j);
+                    self.low[i] = self.low[i].min(self.low[j]);
+                },
+                Some(idx) => if self.on_stack[j] {
+                    self.low[i] = self.low[i].min(idx);
+                }
+            }
+        }
+
+        if Some(self.low[i]) == self.index[i] {
+            let mut scc = vec![];
+            loop {
+                let j = self.stack.pop().unwrap(); // `i` is always on the stack here
+                self.on_stack[j] = false;
+                scc.push(j);
+                if j == i {
+                    break;
+                }
+            }
+            self.sccs.push(scc);
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use std::collections::HashMap;
+
+/// The state of Newton's method after one of its iterations, recorded when 
+/// `Settings::history` is on.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct Iterate {
+    /// The number of the iteration, starting from 1.
+    pub iteration: usize,
+    /// The value of each unknown after the iteration.
+    pub values: HashMap<String, f64>,
+    /// The (absolute) residual of the equation(s) after the iteration.
+    pub residual: f64,
+    /// The Euclidean length of the step taken in the iteration.
+    pub step: f64,
+    /// The estimated condition number of the (scaled) Jacobian that the step was 
+    /// found from. Always `1.0` for single equations.
+    pub condition: f64
+}
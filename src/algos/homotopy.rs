@@ -0,0 +1,140 @@
+use std::{collections::HashMap, error::Error};
+use crate::expr::Expr;
+use super::{Method, Report, Settings, Solution, Variable, linear_step, mv_newton_raphson, residuals, take_step};
+
+/// The first step taken along the path, as a fraction of the whole path.
+const INITIAL_STEP: f64 = 0.05;
+
+/// The largest step taken along the path.
+const MAX_STEP: f64 = 0.25;
+
+/// The smallest step taken along the path before giving up on it.
+const MIN_STEP: f64 = 1E-6;
+
+/// The largest number of Newton iterations made to return to the path after each step.
+const CORRECTOR_ITERATIONS: usize = 6;
+
+/// How closely the path is followed, relative to the residuals of the system at the initial guess.
+const CORRECTOR_TOLERANCE: f64 = 1E-6;
+
+/// Returns the sum of the absolute values in a slice.
+fn l1(v: &[f64]) -> f64 {
+    v.iter().map(|i| i.abs()).sum()
+}
+
+/// Pulls a predicted point back onto the path `F(x) - (1-t)F(x0) = 0` at a fixed `t`
+/// with Newton's method, returning the point and the number of iterations taken. 
+/// Returns `None` if the iterations don't contract, since a smaller step along the
+/// path is needed.
+fn correct<'a>(
+    system: &[Expr], 
+    mut x: HashMap<&'a str, Variable>, 
+    f0: &[f64], 
+    t: f64, 
+    tolerance: f64, 
+    settings: &Settings
+) -> Option<(HashMap<&'a str, Variable>, usize)> {
+
+    let h = |x: &HashMap<&str, Variable>| -> Option<Vec<f64>> {
+        let fx = residuals(system, x).ok()?;
+        Some(fx.iter().zip(f0).map(|(f, f0)| f - (1.0 - t) * f0).collect())
+    };
+
+    let mut hx = h(&x)?;
+    let mut error = l1(&hx);
+
+    for i in 0..=CORRECTOR_ITERATIONS {
+        if error <= tolerance {
+            return Some((x, i))
+        }
+        let (step, _) = linear_step(system, &x, &hx, None, settings).ok()?;
+        x = take_step(&x, &step, 1.0);
+
+        hx = h(&x)?;
+        let e = l1(&hx);
+        if !e.is_finite() || e >= error {
+            return None
+        }
+        error = e;
+    }
+    None
+}
+
+/// Attempts to solve the equations passed to `system` by homotopy continuation.
+/// 
+/// The guess solves the system `F(x) - F(x0) = 0`, which is deformed into the 
+/// real system `F(x) = 0` via `H(x, t) = F(x) - (1 - t)F(x0)` as `t` goes from 0 to 1. 
+/// The path of solutions is tracked with a predictor-corrector scheme: each step
+/// along the path is predicted from the path's tangent and then corrected back 
+/// onto the path with Newton's method. Steps grow while the path is easy to follow 
+/// and shrink when the corrector struggles. Once `t` reaches 1, the solution is 
+/// polished to `settings.tolerance` with `mv_newton_raphson`.
+/// 
+/// This converges from guesses that are far too poor for `mv_newton_raphson`, 
+/// as long as the path doesn't turn back on itself (i.e. the Jacobian doesn't 
+/// become singular along the way). If the path can't be followed to `t = 1`,
+/// the point that was reached is returned as a non-converged solution.
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use nexsys::algos::{Variable, Settings};
+/// use nexsys::algos::homotopy;
+/// use nexsys::expr::Expr;
+/// 
+/// let my_sys = vec![
+///     Expr::parse("x^2 + y - 11").unwrap(), 
+///     Expr::parse("x + y^2 - 7").unwrap()
+/// ];
+/// let guess = HashMap::from([
+///     ("x", Variable::new(2.5, None)),
+///     ("y", Variable::new(2.5, None))
+/// ]);
+/// 
+/// let ans = homotopy(&my_sys, guess, &Settings::default()).unwrap().unwrap();
+/// 
+/// assert_eq!(ans["x"].as_f64().round(), 3.0);
+/// assert_eq!(ans["y"].as_f64().round(), 2.0);
+/// ```
+pub fn homotopy<'a>(system: &[Expr], guess: HashMap<&'a str, Variable>, settings: &Settings) 
+-> Result<Report<HashMap<&'a str, Variable>>, Box<dyn Error>> {
+
+    let f0 = residuals(system, &guess)?;
+    let tolerance = (CORRECTOR_TOLERANCE * (1.0 + l1(&f0))).max(settings.tolerance);
+
+    let mut x = guess;
+    let mut t = 0.0;
+    let mut dt = INITIAL_STEP;
+    let mut count: usize = 0;
+
+    while t < 1.0 {
+        if count > settings.max_iterations || dt < MIN_STEP {
+            let residual = l1(&residuals(system, &x)?);
+            return Ok(Report { solution: Solution::NonConverged(x), iterations: count, residual, undetermined: vec![], criteria: None, history: vec![] })
+        }
+
+        let t_next = (t + dt).min(1.0);
+
+        // predict along the tangent of the path, `J·dx/dt = -F(x0)`
+        let (tangent, _) = linear_step(system, &x, &f0, None, settings)?;
+        let predicted = take_step(&x, &tangent, t_next - t);
+
+        match correct(system, predicted, &f0, t_next, tolerance, settings) {
+            Some((corrected, iterations)) => {
+                count += iterations + 1;
+                x = corrected;
+                t = t_next;
+                if iterations <= 2 {
+                    dt = (2.0 * dt).min(MAX_STEP);
+                }
+            },
+            None => {
+                count += 1;
+                dt /= 2.0;
+            }
+        }
+        settings.monitor.iteration(Method::Homotopy, count, l1(&residuals(system, &x)?))?;
+    }
+
+    let polished = mv_newton_raphson(system, x, settings)?;
+    Ok(Report { iterations: count + polished.iterations, ..polished })
+}
@@ -0,0 +1,112 @@
+use std::{collections::HashMap, error::Error};
+use crate::{expr::Expr, mvcalc::NxN};
+use super::{Method, Report, Settings, Solution, Variable, cap_step, project_step, jacobian_by, residuals, take_step};
+
+/// Attempts to solve the equations passed to `system` via the Levenberg-Marquardt method.
+/// 
+/// Each step solves `(JᵀJ + μ·diag(JᵀJ))δ = -JᵀF`, which blends a Newton step (small `μ`) 
+/// with a short gradient descent step (large `μ`). `μ` is reduced after steps that reduce 
+/// the residual and increased after steps that don't, so this method is slower than 
+/// `mv_newton_raphson` but much less likely to diverge from a poor guess or to fail on a 
+/// singular Jacobian.
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use nexsys::algos::{Variable, Settings};
+/// use nexsys::algos::levenberg_marquardt;
+/// use nexsys::expr::Expr;
+/// 
+/// let my_sys = vec![
+///     Expr::parse("x^2 + y^2 - x - 3").unwrap(), 
+///     Expr::parse("x*y + 0.5*y^2 - 2").unwrap()
+/// ];
+/// let guess = HashMap::from([
+///     ("x", Variable::new(1.0, None)),
+///     ("y", Variable::new(1.0, None)) // the jacobian is singular here
+/// ]);
+/// let ans = levenberg_marquardt(&my_sys, guess, &Settings::default()).unwrap();
+/// 
+/// assert!(ans.converged());
+/// ```
+pub fn levenberg_marquardt<'a>(system: &[Expr], mut guess: HashMap<&'a str, Variable>, settings: &Settings) 
+-> Result<Report<HashMap<&'a str, Variable>>, Box<dyn Error>> {
+
+    let cost = |fx: &[f64]| 0.5 * fx.iter().map(|i| i * i).sum::<f64>();
+    let error = |fx: &[f64]| fx.iter().map(|i| i.abs()).sum::<f64>();
+
+    let mut mu = 1E-3;
+    let f0 = residuals(system, &guess)?;
+    let mut fx = f0.clone();
+    let mut criteria = settings.convergence.measure(&fx, &f0, &f0, &HashMap::new(), &[], &HashMap::new());
+    let mut count: usize = 0;
+
+    while !settings.convergence.met(&criteria, settings.tolerance) {
+        
+        count += 1;
+        if count > settings.max_iterations {
+            return Ok(Report { solution: Solution::NonConverged(guess), iterations: count, residual: error(&fx), undetermined: vec![], criteria: Some(criteria), history: vec![] })
+        }
+        settings.monitor.iteration(Method::LevenbergMarquardt, count, error(&fx))?;
+
+        let j = jacobian_by(system, &guess, settings.derivative)?;
+        let vars = j.vars.clone().unwrap();
+        let cols = j.to_vec(); // cols[c][r] is the derivative of equation r w.r.t. variable c
+
+        // normal equations: a = JᵀJ, g = JᵀF
+        let n = cols.len();
+        let a: Vec<Vec<f64>> = (0..n).map(|c| (0..n).map(|r| dot(&cols[r], &cols[c])).collect()).collect();
+        let g: Vec<f64> = cols.iter().map(|c| dot(c, &fx)).collect();
+
+        if g.iter().all(|i| i.abs() < f64::EPSILON) {
+            break // stuck at a stationary point of the residual that isn't a solution
+        }
+
+        let mut damped = a.clone();
+        for (i, col) in damped.iter_mut().enumerate() {
+            col[i] += mu * a[i][i].max(1E-12);
+        }
+
+        let dx = match NxN::from_cols(damped, None)?.solve(&g) {
+            Ok(dx) => dx,
+            Err(_) => {
+                mu *= 10.0;
+                continue
+            }
+        };
+
+        let mut step = HashMap::new();
+        for (k, dx) in vars.into_iter().zip(dx) {
+            step.insert(k, -dx);
+        }
+        let full = step.clone();
+        cap_step(&mut step, settings);
+        project_step(&mut step, &guess);
+
+        let trial = take_step(&guess, &step, 1.0);
+        let f_trial = residuals(system, &trial)?;
+
+        if f_trial.iter().all(|i| i.is_finite()) && cost(&f_trial) < cost(&fx) {
+            criteria = settings.convergence.measure(&f_trial, &fx, &f0, &full, &[], &HashMap::new());
+            guess = trial;
+            fx = f_trial;
+            mu = (mu / 3.0).max(1E-12);
+        } else {
+            mu *= 4.0;
+            if mu > 1E16 {
+                break // the step has shrunk to nothing without improving the residual
+            }
+        }
+    }
+
+    let residual = error(&fx);
+    if settings.convergence.met(&criteria, settings.tolerance) {
+        Ok(Report { solution: Solution::Converged(guess), iterations: count, residual, undetermined: vec![], criteria: Some(criteria), history: vec![] })
+    } else {
+        Ok(Report { solution: Solution::NonConverged(guess), iterations: count, residual, undetermined: vec![], criteria: Some(criteria), history: vec![] })
+    }
+}
+
+/// Returns the dot product of two equal-length slices.
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(i, j)| i * j).sum()
+}
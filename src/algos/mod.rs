@@ -1,13 +1,34 @@
 mod structs;
-
-use meval::{Context, eval_str_with_context};
+mod decompose;
+mod lm;
+mod brent;
+mod broyden;
+mod homotopy;
+mod continuation;
+mod multistart;
+mod convergence;
+mod history;
+
+use meval::Context;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::error::Error;
-use crate::errors::SolverDivisionByZeroError;
-use crate::mvcalc::{jacobian, functionify, mat_vec_mul, d_dx};
+use std::fmt::{self, Display};
+use crate::errors::{SolverConvergenceError, SolverDivisionByZeroError};
+use crate::expr::Expr;
+use crate::mvcalc::{jacobian, jacobian_rows, numeric_jacobian, sparse_jacobian, numeric_sparse_jacobian, d_dx, Dual, NxN, MAX_CONDITION};
 use crate::parsing::conditional;
 
 pub use structs::*;
+pub use decompose::*;
+pub use lm::*;
+pub use brent::*;
+pub use broyden::*;
+pub use homotopy::*;
+pub use continuation::*;
+pub use multistart::*;
+pub use convergence::*;
+pub use history::*;
 
 /// Enum used to indicate whether the solution produced converged or not.
 pub enum Solution<T> {
@@ -25,6 +46,83 @@ impl <T> Solution<T> {
     }
 }
 
+/// A `Solution` along with a summary of the work that was done to obtain it.
+pub struct Report<T> {
+    /// The solution produced by the algorithm.
+    pub solution: Solution<T>,
+    /// The number of iterations the algorithm took.
+    pub iterations: usize,
+    /// The (absolute) residual of the equation(s) at the solution.
+    pub residual: f64,
+    /// The variables that the Jacobian couldn't determine at some point during the 
+    /// solve, because it was (nearly) singular. Steps in these directions are found 
+    /// via the pseudo-inverse of the Jacobian. Always empty for single equations.
+    pub undetermined: Vec<String>,
+    /// The values that the convergence criteria (see `Settings::convergence`) reached,
+    /// for the algorithms that measure them.
+    pub criteria: Option<Criteria>,
+    /// The state after each iteration, if `Settings::history` is on and the algorithm 
+    /// records it (`newton_raphson` and `mv_newton_raphson`), otherwise empty.
+    pub history: Vec<Iterate>
+}
+impl <T> Report<T> {
+    /// Returns the contained value, consuming the `self` value. 
+    /// Like `Solution::unwrap`, this method never panics.
+    pub fn unwrap(self) -> T {
+        self.solution.unwrap()
+    }
+
+    /// Returns `true` if the contained solution converged.
+    pub fn converged(&self) -> bool {
+        matches!(self.solution, Solution::Converged(_))
+    }
+}
+
+/// The algorithms that Nexsys can use to solve equations.
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Method {
+    /// Single-variable Newton-Raphson method. (`newton_raphson`)
+    Newton,
+    /// Golden section search. (`golden_search`)
+    GoldenSection,
+    /// Multivariate Newton-Raphson method. (`mv_newton_raphson`)
+    MvNewton,
+    /// Direct isolation of an unknown that appears linearly. (`direct`)
+    Direct,
+    /// Levenberg-Marquardt method. (`levenberg_marquardt`)
+    LevenbergMarquardt,
+    /// Brent's bracketing method. (`brent`)
+    Brent,
+    /// A search for every root in a domain, followed by a choice of one root. (`all_roots`)
+    AllRoots,
+    /// Broyden's quasi-Newton method. (`broyden`)
+    Broyden,
+    /// Homotopy continuation from an easier system. (`homotopy`)
+    Homotopy,
+    /// Newton's method from several guesses, with deflation. (`multistart`)
+    Multistart
+}
+impl Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Method::Newton          => write!(f, "newton-raphson"),
+            Method::GoldenSection   => write!(f, "golden section search"),
+            Method::MvNewton        => write!(f, "multivariate newton-raphson"),
+            Method::Direct          => write!(f, "direct solution"),
+            Method::LevenbergMarquardt => write!(f, "levenberg-marquardt"),
+            Method::Brent           => write!(f, "brent's method"),
+            Method::AllRoots        => write!(f, "root search"),
+            Method::Broyden         => write!(f, "broyden's method"),
+            Method::Homotopy        => write!(f, "homotopy continuation"),
+            Method::Multistart      => write!(f, "multistart with deflation")
+        }
+    }
+}
+
 /// Function for returning non-empty context with Nexsys-custom functions. E.g. `if()`.
 pub fn new_context() -> Context<'static> {
     let mut ctx = Context::new();
@@ -32,40 +130,235 @@ pub fn new_context() -> Context<'static> {
     ctx
 }
 
-/// Performs one iteration of Newton's method for a system of equations, returning the next guess vector. 
-fn next_guess<'a>(system: &Vec<&'a str>, mut guess: HashMap<&'a str, Variable>) -> Result<HashMap<&'a str, Variable>, Box<dyn Error>> {
+/// A step for each variable, along with the variables that the step couldn't determine.
+type Step = (HashMap<String, f64>, Vec<String>);
+
+/// Returns the Newton step for a system of equations, i.e. the change in each variable 
+/// that zeroes the linearization of the system at `guess`. Systems larger than 
+/// `settings.sparse_threshold` are linearized with a sparse Jacobian.
+fn newton_step(system: &[Expr], guess: &HashMap<&str, Variable>, scaling: Option<&Scaling>, settings: &Settings) -> Result<Step, Box<dyn Error>> {
+    linear_step(system, guess, &residuals(system, guess)?, scaling, settings)
+}
+
+/// Solves `J·dx = -rhs` for `dx`, where `J` is the Jacobian of a system at `guess`.
+/// Systems larger than `settings.sparse_threshold` use a sparse Jacobian. If a scaling 
+/// is given, the rows and columns of `J` are scaled before it is factored.
+/// 
+/// If `J` is singular or nearly singular, the minimum-norm least-squares step is found 
+/// via the pseudo-inverse of `J` instead, and the variables in the directions that `J` 
+/// can't determine are returned with the step.
+fn linear_step(system: &[Expr], guess: &HashMap<&str, Variable>, rhs: &[f64], scaling: Option<&Scaling>, settings: &Settings) -> Result<Step, Box<dyn Error>> {
+
+    let (rows, rhs): (Vec<f64>, Vec<f64>) = match scaling {
+        Some(s) => (s.rows.iter().map(|i| 1.0 / i).collect(), s.residuals(rhs)),
+        None => (vec![1.0; rhs.len()], rhs.to_vec())
+    };
+    let scale_of = |vars: &Option<Vec<String>>| -> Vec<f64> {
+        vars.iter().flatten().map(|v| scaling.map_or(1.0, |s| s.col(v))).collect()
+    };
+    let unscale = |x: Vec<f64>, cols: &[f64]| -> Vec<f64> {
+        x.iter().zip(cols).map(|(a, b)| a * b).collect()
+    };
+
+    let (j, cols, lu) = if system.len() > settings.sparse_threshold {
+        let mut j = match settings.derivative {
+            Derivative::Analytic => sparse_jacobian(system, guess)?,
+            Derivative::FiniteDifference => numeric_sparse_jacobian(system, guess)?
+        };
+        let cols = scale_of(&j.vars);
+        j.scale(&rows, &cols);
+        match j.lu() {
+            Ok(lu) if lu.condition() < MAX_CONDITION => return Ok(negated_step(j.vars, unscale(lu.solve(&rhs)?, &cols), vec![])),
+            lu => {
+                // (nearly) singular, so fall back to the dense checks below
+                let j = j.to_dense()?;
+                let lu = lu.and_then(|_| j.lu());
+                (j, cols, lu)
+            }
+        }
+    } else {
+        let mut j = jacobian_by(system, guess, settings.derivative)?;
+        let cols = scale_of(&j.vars);
+        j.scale(&rows, &cols);
+        let lu = j.lu();
+        (j, cols, lu)
+    };
+
+    match lu {
+        Ok(lu) if lu.condition() < MAX_CONDITION => Ok(negated_step(j.vars, unscale(lu.solve(&rhs)?, &cols), vec![])),
+        Ok(_) | Err(_) if (0..j.size).all(|r| j.get_row(r).iter().all(|i| i.is_finite())) => {
+            let svd = j.svd();
+            Ok(negated_step(j.vars, unscale(svd.solve(&rhs)?, &cols), svd.undetermined()))
+        },
+        Ok(_) => Err(Box::new(SolverConvergenceError::default())),
+        Err(e) => Err(e)
+    }
+}
+
+/// Returns the estimated condition number of the Jacobian of a system at `guess`, 
+/// scaled in the same way as in `linear_step`.
+fn jacobian_condition(system: &[Expr], guess: &HashMap<&str, Variable>, scaling: Option<&Scaling>, settings: &Settings) -> f64 {
+    let Ok(mut j) = jacobian_by(system, guess, settings.derivative) else {
+        return f64::INFINITY
+    };
+    if let Some(s) = scaling {
+        let rows: Vec<f64> = s.rows.iter().map(|i| 1.0 / i).collect();
+        let cols: Vec<f64> = j.vars.iter().flatten().map(|v| s.col(v)).collect();
+        j.scale(&rows, &cols);
+    }
+    j.condition()
+}
 
-    let mut j = jacobian(system, &guess)?;
-    j.invert()?;
+/// Scale factors that bring the unknowns and residuals of a system to order one.
+struct Scaling {
+    /// The typical magnitude of each variable.
+    cols: HashMap<String, f64>,
+    /// The typical size of the terms in each equation.
+    rows: Vec<f64>
+}
+impl Scaling {
+    /// Finds the scaling of a system at `guess`. Each variable is scaled by its magnitude in 
+    /// `settings.typical`, or else by its guess value, or else by the largest bound of its 
+    /// domain. Each equation is then scaled by the largest entry in its row of the 
+    /// (column-scaled) Jacobian.
+    fn new(system: &[Expr], guess: &HashMap<&str, Variable>, settings: &Settings) -> Scaling {
+        let magnitude = |x: f64| if x.is_finite() && x > 0.0 { Some(x) } else { None };
+
+        let cols: HashMap<String, f64> = guess.iter()
+            .map(|(k, v)| {
+                let d = settings.typical.get(*k).and_then(|i| magnitude(i.abs()))
+                    .or_else(|| magnitude(v.as_f64().abs()))
+                    .or_else(|| v.get_domain().and_then(|[lo, hi]| magnitude(lo.abs().max(hi.abs()))))
+                    .unwrap_or(1.0);
+                (k.to_string(), d)
+            })
+            .collect();
+
+        let vars: Vec<&str> = guess.keys().copied().collect();
+        let rows = match jacobian_rows(system, guess, &vars) {
+            Ok(j) => j.iter()
+                .map(|row| {
+                    let largest = row.iter().zip(&vars).map(|(a, v)| a.abs() * cols[*v]).fold(0.0, f64::max);
+                    magnitude(largest).unwrap_or(1.0)
+                })
+                .collect(),
+            Err(_) => vec![1.0; system.len()]
+        };
+
+        Scaling { cols, rows }
+    }
 
-    let mut err = None;
+    /// Returns the typical magnitude of a variable.
+    fn col(&self, var: &str) -> f64 {
+        self.cols.get(var).copied().unwrap_or(1.0)
+    }
 
-    let fx = Vec::from_iter(
-        system.iter().map(
-            |&i| match functionify(i)(&guess) {
-                Ok(o) => o,
-                Err(e) => {
-                    err = Some(e);  // same song and dance as in mvcalc...
-                    f64::NAN        // ...this fn returns f64, the overall function returns Result
-                }
+    /// Divides each residual by the scale of its equation.
+    fn residuals(&self, fx: &[f64]) -> Vec<f64> {
+        fx.iter().zip(&self.rows).map(|(f, r)| f / r).collect()
+    }
+}
+
+/// Pairs the negated solution of a linear system with the variables of the Jacobian's columns.
+fn negated_step(vars: Option<Vec<String>>, x_n: Vec<f64>, undetermined: Vec<String>) -> Step {
+    (
+        stitch_hm(
+            vars.unwrap(), // jacobians always name their columns
+            x_n.iter().map(|i| -i).collect()
+        ),
+        undetermined
+    )
+}
+
+/// Returns the Jacobian of a system, found the way given by `derivative`.
+fn jacobian_by(system: &[Expr], guess: &HashMap<&str, Variable>, derivative: Derivative) -> Result<NxN, Box<dyn Error>> {
+    match derivative {
+        Derivative::Analytic => jacobian(system, guess),
+        Derivative::FiniteDifference => numeric_jacobian(system, guess)
+    }
+}
+
+/// Evaluates each equation in a system at `guess`.
+fn residuals(system: &[Expr], guess: &HashMap<&str, Variable>) -> Result<Vec<f64>, Box<dyn Error>> {
+    system.iter()
+        .map(|i| i.eval(&|v| guess.get(v).map(|x| x.as_f64())))
+        .collect()
+}
+
+/// Shrinks a step (keeping its direction) so that no variable moves 
+/// further than the cap given for it in `settings.max_step`.
+fn cap_step(step: &mut HashMap<String, f64>, settings: &Settings) {
+    let mut factor: f64 = 1.0;
+    for (var, dx) in step.iter() {
+        if let Some(cap) = settings.max_step.get(var) {
+            if dx.abs() > *cap {
+                factor = factor.min(cap / dx.abs());
             }
-        )
-    );
+        }
+    }
+    for dx in step.values_mut() {
+        *dx *= factor;
+    }
+}
 
-    if let Some(e) = err {
-        return Err(e)
+/// Projects a step onto the domains of the variables in `guess`. Variables that are 
+/// already on a bound and would be pushed past it are held in place, and the rest of 
+/// the step is shortened (keeping its direction) so that it stops at the first bound 
+/// it reaches, rather than being clamped to the bounds, which bends the step.
+fn project_step(step: &mut HashMap<String, f64>, guess: &HashMap<&str, Variable>) {
+    let mut factor: f64 = 1.0;
+    for (var, dx) in step.iter_mut() {
+        let (x, [lo, hi]) = match guess.get(var.as_str()).and_then(|v| Some((v.as_f64(), v.get_domain()?))) {
+            Some(d) => d,
+            None => continue
+        };
+
+        let room = if *dx > 0.0 { hi - x } else { lo - x };
+        if room == 0.0 {
+            *dx = 0.0; // pinned on a bound
+        } else if dx.abs() > room.abs() {
+            factor = factor.min(room / *dx);
+        }
+    }
+    for dx in step.values_mut() {
+        *dx *= factor;
     }
+}
 
-    let x_n = stitch_hm(
-        j.vars.clone().unwrap(), // don't want to clone here, but Vec<String> shouldn't be super costly
-        mat_vec_mul(j, fx)?
-    );
+/// Finds the fraction `λ` of a Newton step to take by backtracking. Starting from a full step, 
+/// `λ` is halved until the merit function `φ = ½|F|²` satisfies the Armijo condition 
+/// `φ(λ) <= (1 - 2cλ)φ(0)`, where `trial` returns `φ(λ)` and `merit` is `φ(0)`.
+/// 
+/// If no acceptable `λ` is found, the smallest step tried is returned so that the
+/// calling algorithm can keep making progress.
+fn backtrack(merit: f64, mut trial: impl FnMut(f64) -> Result<f64, Box<dyn Error>>) -> Result<f64, Box<dyn Error>> {
+    const ARMIJO: f64 = 1E-4;
+    const MIN_LAMBDA: f64 = 1.0 / 1024.0;
+
+    let mut lambda = 1.0;
+    while lambda > MIN_LAMBDA {
+        let m = trial(lambda)?;
+        if m.is_finite() && m <= (1.0 - 2.0 * ARMIJO * lambda) * merit {
+            break
+        }
+        lambda /= 2.0;
+    }
+    Ok(lambda)
+}
 
-    for v in &mut guess {
-        v.1.step(-x_n[&v.0.to_string()])
+/// Returns a copy of `guess` moved by `lambda` times `step`.
+fn take_step<'a>(guess: &HashMap<&'a str, Variable>, step: &HashMap<String, f64>, lambda: f64) -> HashMap<&'a str, Variable> {
+    let mut res = guess.clone();
+    for (k, v) in &mut res {
+        v.step(lambda * step[*k]);
     }
+    res
+}
 
-    Ok(guess)
+/// Returns the step that each variable actually took between two iterates, 
+/// after any domains were applied.
+fn taken(before: &HashMap<&str, Variable>, after: &HashMap<&str, Variable>) -> HashMap<String, f64> {
+    after.iter().map(|(k, v)| (k.to_string(), v.as_f64() - before[k].as_f64())).collect()
 }
 
 /// Reverses the operation performed by `split_hm`.
@@ -83,118 +376,217 @@ where
     res
 }
 
+/// Solves a single equation for a single unknown value by isolating the unknown,
+/// without iterating. Returns `None` if the equation is not linear in the unknown
+/// or if the unknown cancels out of the equation.
+/// 
+/// The solution is reported as non-converged if it lies outside of the unknown's 
+/// domain or if rounding error leaves a residual greater than `tolerance`.
+/// 
+/// # Example
+/// ```
+/// use nexsys::algos::Variable;
+/// use nexsys::algos::direct;
+/// use nexsys::expr::Expr;
+/// 
+/// let my_eqn = Expr::parse("7 - (3*x + 2)").unwrap();
+/// let my_guess = ("x", Variable::new(1.0, None));
+/// 
+/// let root = direct(&my_eqn, my_guess, 1E-10).unwrap().unwrap();
+/// 
+/// assert_eq!(root.1.as_f64(), 5.0 / 3.0)
+/// ```
+pub fn direct<'a>(equation: &Expr, guess: (&'a str, Variable), tolerance: f64) -> Option<Report<(&'a str, Variable)>> {
+
+    let (a, b) = equation.linear(guess.0)?;
+    if a == 0.0 || !a.is_finite() || !b.is_finite() {
+        return None
+    }
+
+    let mut xi = guess.1;
+    xi.change(-b / a);
+
+    let residual = equation.eval(&|v| if v == guess.0 { Some(xi.as_f64()) } else { None })
+        .ok()?
+        .abs();
+
+    let solution = if residual <= tolerance {
+        Solution::Converged((guess.0, xi))
+    } else {
+        Solution::NonConverged((guess.0, xi))
+    };
+
+    Some(Report { solution, iterations: 0, residual, undetermined: vec![], criteria: None, history: vec![] })
+}
+
 /// Solves a single equation for a single unknown value. 
 /// `mv_newton_raphson` can also be used for this scenario, but this 
 /// function is a more lightweight and reasonable choice.
 /// 
 /// # Example
 /// ```
-/// use nexsys::algos::Variable;
+/// use nexsys::algos::{Variable, Settings};
 /// use nexsys::algos::newton_raphson;
+/// use nexsys::expr::Expr;
 /// 
-/// let my_eqn = "x^2 - 1";
+/// let my_eqn = Expr::parse("x^2 - 1").unwrap();
 /// let my_guess = ("x", Variable::new(-5.0, Some([-10.0, 0.0])));
 /// 
-/// let root = newton_raphson(my_eqn, my_guess, 0.001, 500).unwrap().unwrap();
+/// let root = newton_raphson(&my_eqn, my_guess, &Settings::new(0.001, 500)).unwrap().unwrap();
 /// 
 /// assert_eq!(root.1.as_f64().round(), -1.0)
 /// ```
-pub fn newton_raphson<'a>(equation: &'a str, guess: (&'a str, Variable), tolerance: f64, max_iterations: usize) 
--> Result<Solution<(&'a str, Variable)>, Box<dyn Error>> {
+pub fn newton_raphson<'a>(equation: &Expr, guess: (&'a str, Variable), settings: &Settings) 
+-> Result<Report<(&'a str, Variable)>, Box<dyn Error>> {
 
     let mut xi = guess.1;
-    let mut ctx = new_context();
-    ctx.funcn("if", conditional, 5_usize);
     
-    // Lord, forgive me for what I am about to do...
-    let mut f = |x:f64| -> Result<f64, Box<dyn Error>> {
-        match eval_str_with_context(equation, ctx.var(guess.0, x)) {
-            Ok(o) => Ok(o.abs()),
-            Err(e) => Err(Box::new(e))
+    let f = |x:f64| -> Result<f64, Box<dyn Error>> {
+        equation.eval(&|v| if v == guess.0 { Some(x) } else { None })
+    };
+
+    let df = |x:f64| -> Result<f64, Box<dyn Error>> {
+        match settings.derivative {
+            Derivative::Analytic => Ok(equation.eval_dual(&|v| if v == guess.0 { Some(Dual::var(x)) } else { None })?.du),
+            Derivative::FiniteDifference => d_dx(f, x)
         }
     };
 
+    let f0 = [f(xi.as_f64())?];
+    let measure = |fx: f64, last: f64, dx: &HashMap<String, f64>| settings.convergence.measure(&[fx], &[last], &f0, dx, &[], &HashMap::new());
+    let mut criteria = measure(f0[0], f0[0], &HashMap::new());
+
     let mut count: usize = 0;
-    while f(xi.as_f64())? > tolerance {
+    let mut history = vec![];
+    while !settings.convergence.met(&criteria, settings.tolerance) {
 
-        let roc = d_dx(&mut f, xi.as_f64())?;
+        let roc = df(xi.as_f64())?;
 
         if roc == 0.0 { return Err(Box::new(SolverDivisionByZeroError::NewtonRaphsonDivByZeroError)) } // Avoid crash
         
-        xi.step( -&f(xi.as_f64())? / roc );
+        let fx = f(xi.as_f64())?;
+        let mut step = HashMap::from([(guess.0.to_string(), -fx / roc)]);
+        let full = step.clone();
+        cap_step(&mut step, settings);
+        project_step(&mut step, &HashMap::from([(guess.0, xi.clone())]));
+        let dx = step[guess.0];
+
+        let lambda = if settings.line_search {
+            backtrack(0.5 * fx * fx, |lambda| {
+                let mut trial = xi.clone();
+                trial.step(lambda * dx);
+                Ok(0.5 * f(trial.as_f64())?.powi(2))
+            })?
+        } else {
+            1.0
+        };
+
+        let before = xi.as_f64();
+        xi.step(lambda * dx);
+        criteria = measure(f(xi.as_f64())?, fx, &full);
         
         count += 1;
-        if count > max_iterations {
-            return Ok(Solution::NonConverged((guess.0, xi)))
+        settings.monitor.iteration(Method::Newton, count, criteria.equations[0])?;
+        if settings.history {
+            history.push(Iterate {
+                iteration: count,
+                values: HashMap::from([(guess.0.to_string(), xi.as_f64())]),
+                residual: criteria.equations[0],
+                step: (xi.as_f64() - before).abs(),
+                condition: 1.0 // a nonzero derivative is always perfectly conditioned
+            });
+        }
+        if count > settings.max_iterations {
+            let residual = f(xi.as_f64())?.abs();
+            return Ok(Report { solution: Solution::NonConverged((guess.0, xi)), iterations: count, residual, undetermined: vec![], criteria: Some(criteria), history })
         }
     }
-    Ok(Solution::Converged((guess.0, xi)))
+    let residual = f(xi.as_f64())?.abs();
+    Ok(Report { solution: Solution::Converged((guess.0, xi)), iterations: count, residual, undetermined: vec![], criteria: Some(criteria), history })
 }
 
 /// Attempts to solve the equations passed to `system` via the Newton-Raphson method.
 /// # Example
 /// ```
 /// use std::collections::HashMap;
-/// use nexsys::algos::Variable;
+/// use nexsys::algos::{Variable, Settings};
 /// use nexsys::algos::mv_newton_raphson;
+/// use nexsys::expr::Expr;
 /// 
-/// let my_sys = vec!["x^2 + y", "y - x"];
+/// let my_sys = vec![
+///     Expr::parse("x^2 + y").unwrap(), 
+///     Expr::parse("y - x").unwrap()
+/// ];
 /// let guess = HashMap::from([
 ///     ("x", Variable::new(1.0, None)),
 ///     ("y", Variable::new(1.0, None))
 /// ]);
-/// let ans = mv_newton_raphson(my_sys, guess, 0.001, 500).unwrap().unwrap();
+/// let ans = mv_newton_raphson(&my_sys, guess, &Settings::new(0.001, 500)).unwrap().unwrap();
 /// 
 /// println!("{:#?}", ans);
 ///
 /// assert_eq!(ans["x"].as_f64().round(), 0.0)
 /// ```
-pub fn mv_newton_raphson<'a>( system: Vec<&'a str>, mut guess: HashMap<&'a str, Variable>, tolerance: f64, max_iterations: usize ) 
--> Result<Solution<HashMap<&'a str, Variable>>, Box<dyn Error>> {
-
-    let error = |guess: &HashMap<&str, Variable>| -> Result<f64, Box<dyn Error>> {
-        let mut err = None;
-        let residual = system.iter().map(
-            |&i| {
-                let mut ctx = new_context();
-                
-                for j in guess {
-                    ctx.var(*j.0, j.1.as_f64()); 
-                }
-                
-                let exp = i.replace('=', "-");
-                
-                match eval_str_with_context(exp, ctx) {
-                    Ok(o) => o.abs(),
-                    Err(e) => {
-                        err = Some(e);
-                        f64::NAN
-                    }
-                }
-            }
-        ).sum();
+pub fn mv_newton_raphson<'a>( system: &[Expr], mut guess: HashMap<&'a str, Variable>, settings: &Settings ) 
+-> Result<Report<HashMap<&'a str, Variable>>, Box<dyn Error>> {
 
-        if let Some(e) = err {
-            return Err(Box::new(e))
-        }
-        Ok(residual)
+    let scaling = if settings.scaling { Some(Scaling::new(system, &guess, settings)) } else { None };
+    let scaled = |fx: Vec<f64>| match &scaling {
+        Some(s) => s.residuals(&fx),
+        None => fx
     };
+    let (rows, cols) = scaling.as_ref().map(|s| (s.rows.clone(), s.cols.clone())).unwrap_or_default();
+    let merit = |fx: &[f64]| 0.5 * fx.iter().map(|i| i * i).sum::<f64>();
     
+    let f0 = residuals(system, &guess)?;
     let mut count: usize = 0;
+    let mut undetermined: Vec<String> = vec![];
+    let mut history = vec![];
 
     loop {
-        let res = next_guess(&system, guess)?;
+        let condition = if settings.history { jacobian_condition(system, &guess, scaling.as_ref(), settings) } else { 0.0 };
+        let (mut step, missing) = newton_step(system, &guess, scaling.as_ref(), settings)?;
+        for v in missing {
+            if !undetermined.contains(&v) {
+                undetermined.push(v);
+            }
+        }
+        let full = step.clone();
+        cap_step(&mut step, settings);
+        project_step(&mut step, &guess);
+
+        let last = residuals(system, &guess)?;
+        let lambda = if settings.line_search {
+            let m = merit(&scaled(last.clone()));
+            backtrack(m, |lambda| Ok(merit(&scaled(residuals(system, &take_step(&guess, &step, lambda))?))))?
+        } else {
+            1.0
+        };
+        let next = take_step(&guess, &step, lambda);
+        let dx = taken(&guess, &next);
+        guess = next;
         
-        let e = error(&res)?;
-        guess = res;
-
-        if e < tolerance { // Solution is valid and acceptable
-            return Ok(Solution::Converged(guess))
-        } else if count > max_iterations { // Solution is valid, but timed out. Add a warning
-            println!("count: {count}\nerror{e}");
-            return Ok(Solution::NonConverged(guess))
-        } 
+        let fx = residuals(system, &guess)?;
+        let e = fx.iter().map(|i| i.abs()).sum::<f64>();
+        let criteria = settings.convergence.measure(&fx, &last, &f0, &full, &rows, &cols);
+
         count += 1;
+        settings.monitor.iteration(Method::MvNewton, count, e)?;
+        if settings.history {
+            history.push(Iterate {
+                iteration: count,
+                values: guess.iter().map(|(k, v)| (k.to_string(), v.as_f64())).collect(),
+                residual: e,
+                step: Norm::Two.of(&dx.values().copied().collect::<Vec<f64>>()),
+                condition
+            });
+        }
+
+        if settings.convergence.met(&criteria, settings.tolerance) { // Solution is valid and acceptable
+            return Ok(Report { solution: Solution::Converged(guess), iterations: count, residual: e, undetermined, criteria: Some(criteria), history })
+        } else if count > settings.max_iterations { // Solution is valid, but timed out. Add a warning
+            return Ok(Report { solution: Solution::NonConverged(guess), iterations: count, residual: e, undetermined, criteria: Some(criteria), history })
+        }
     }
 }
 
@@ -202,41 +594,42 @@ pub fn mv_newton_raphson<'a>( system: Vec<&'a str>, mut guess: HashMap<&'a str,
 /// This function is a more robust substitute for `newton_raphson()`,
 /// although it can take significantly longer to return a result. (Time increases w.r.t. a decrease in tolerance)
 /// 
+/// Note that this minimizes `|f|` rather than finding a root, so it can return a local 
+/// minimum of `|f|` that is not a root. `brent()` should be preferred for root finding.
+/// 
 /// # Example
 /// ```
 /// use nexsys::algos::Variable;
 /// use nexsys::algos::golden_search;
+/// use nexsys::expr::Expr;
 /// 
-/// let my_eqn = "x^2 - 1";
+/// let my_eqn = Expr::parse("x^2 - 1").unwrap();
 /// let my_guess = ("x", Variable::new(-1.0, Some([-10.0, 0.0])));
 /// 
-/// let root = golden_search(my_eqn, my_guess, 0.001).unwrap().unwrap();
+/// let root = golden_search(&my_eqn, my_guess, 0.001).unwrap().unwrap();
 /// 
 /// assert_eq!(root.1.as_f64().round(), -1.0)
 /// ```
-pub fn golden_search<'a>(equation: &'a str, guess: (&'a str, Variable), tolerance: f64) 
--> Result<Solution<(&'a str, Variable)>, Box<dyn Error>> {
+pub fn golden_search<'a>(equation: &Expr, guess: (&'a str, Variable), tolerance: f64) 
+-> Result<Report<(&'a str, Variable)>, Box<dyn Error>> {
 
     let gr = (5_f64.sqrt() + 1.0) / 2.0;
     let mut xi = guess.1;
-    let mut ctx = new_context();
 
     let (mut a, mut d) = match xi.get_domain() {
         Some(d) => (d[0], d[1]),
         None => (-1E20, 1E20)
     };
 
-    // Lord, forgive me for what I am about to do...
-    let mut f = |x:f64| -> Result<f64, Box<dyn Error>> {
-        match eval_str_with_context(equation, ctx.var(guess.0, x)) {
-            Ok(o) => Ok(o.abs()),
-            Err(e) => Err(Box::new(e)) 
-        }
+    let f = |x:f64| -> Result<f64, Box<dyn Error>> {
+        Ok(equation.eval(&|v| if v == guess.0 { Some(x) } else { None })?.abs())
     };
 
     let mut b = d - (d - a) / gr;
     let mut c = a + (d - a) / gr;
+    let mut count: usize = 0;
     while (d - a).abs() > tolerance {
+        count += 1;
 
         if f(b)? < f(c)? {
             d = c;
@@ -249,6 +642,7 @@ pub fn golden_search<'a>(equation: &'a str, guess: (&'a str, Variable), toleranc
     }
 
     xi.change((d + a)  / 2.0);
+    let residual = f(xi.as_f64())?;
 
-    Ok(Solution::Converged((guess.0, xi))) // this is to maintain interchangeability with `newton_raphson()`
+    Ok(Report { solution: Solution::Converged((guess.0, xi)), iterations: count, residual, undetermined: vec![], criteria: None, history: vec![] }) // this is to maintain interchangeability with `newton_raphson()`
 }
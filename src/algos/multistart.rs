@@ -0,0 +1,211 @@
+use std::{collections::HashMap, error::Error};
+use serde::{Deserialize, Serialize};
+use crate::{expr::{Expr, Op}, errors::DomainRequiredError, monitor::is_interruption};
+use super::{Report, Settings, Solution, Variable, mv_newton_raphson, residuals};
+
+/// How the initial guesses for a multistart search are sampled from the domains of the unknowns.
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sampling {
+    /// Each value is drawn uniformly from its domain.
+    Random,
+    /// Each domain is split into as many intervals as there are guesses, and each 
+    /// interval is used exactly once, which spreads the guesses out more evenly.
+    LatinHypercube
+}
+
+/// Options for searching for every solution of a block of equations (see `multistart`).
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Multistart {
+    /// The number of initial guesses to try, including the guess that was given.
+    pub starts: usize,
+    /// How the initial guesses are sampled.
+    pub sampling: Sampling,
+    /// The seed for the random number generator, so that searches can be repeated.
+    pub seed: u64
+}
+impl Default for Multistart {
+    fn default() -> Multistart {
+        Multistart { starts: 20, sampling: Sampling::LatinHypercube, seed: 0 }
+    }
+}
+
+/// Shorthand for the solutions of a system found by `multistart`.
+pub type Solutions<'a> = Vec<HashMap<&'a str, Variable>>;
+
+/// A small pseudo-random number generator (SplitMix64).
+struct Rng(u64);
+impl Rng {
+    /// Returns the next random integer.
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a random value on `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Samples `n` points from the box given by `domains`.
+fn sample(n: usize, domains: &[[f64; 2]], options: &Multistart) -> Vec<Vec<f64>> {
+    let mut rng = Rng(options.seed);
+    let mut points = vec![vec![0.0; domains.len()]; n];
+
+    for (d, [lo, hi]) in domains.iter().enumerate() {
+        let width = hi - lo;
+        match options.sampling {
+            Sampling::Random => for p in points.iter_mut() {
+                p[d] = lo + width * rng.next_f64();
+            },
+            Sampling::LatinHypercube => {
+                // shuffle the intervals (Fisher-Yates) so each one is used once
+                let mut cells: Vec<usize> = (0..n).collect();
+                for i in (1..n).rev() {
+                    cells.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+                }
+                for (p, c) in points.iter_mut().zip(cells) {
+                    p[d] = lo + width * (c as f64 + rng.next_f64()) / n as f64;
+                }
+            }
+        }
+    }
+    points
+}
+
+/// Returns the system multiplied by the deflation operator `Π (1 + 1/|x - r|²)` over 
+/// every root `r` that was already found, where distances are measured relative to 
+/// the width of each domain. The deflated system has the same roots as the original,
+/// except that the found roots are removed, so Newton's method is pushed away from them.
+fn deflate(system: &[Expr], roots: &[HashMap<&str, Variable>], vars: &[(&str, f64)]) -> Vec<Expr> {
+    let bin = |op, a, b| Expr::Bin(op, Box::new(a), Box::new(b));
+
+    let factor = roots.iter()
+        .map(|r| {
+            let dist = vars.iter()
+                .map(|&(v, width)| bin(
+                    Op::Pow, 
+                    bin(Op::Div, bin(Op::Sub, Expr::Var(v.to_string()), Expr::Num(r[v].as_f64())), Expr::Num(width)), 
+                    Expr::Num(2.0)
+                ))
+                .reduce(|a, b| bin(Op::Add, a, b))
+                .unwrap_or(Expr::Num(0.0));
+            bin(Op::Add, Expr::Num(1.0), bin(Op::Div, Expr::Num(1.0), dist))
+        })
+        .reduce(|a, b| bin(Op::Mul, a, b));
+
+    match factor {
+        Some(m) => system.iter().map(|i| bin(Op::Mul, m.clone(), i.clone())).collect(),
+        None => system.to_vec()
+    }
+}
+
+/// Searches for every solution of a system of equations inside the domains of its unknowns.
+/// 
+/// `mv_newton_raphson` is run from the given guess and from `options.starts - 1` more guesses 
+/// sampled from the domains (so every unknown must have a domain, otherwise a `DomainRequiredError` 
+/// is returned). Each solution that is found is deflated out of the system, so later runs are 
+/// steered towards solutions that haven't been found yet, and each run is repeated from the 
+/// same guess until it stops finding new solutions.
+/// 
+/// The distinct solutions are returned in the order they were found. The report is only 
+/// converged if at least one solution was found.
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use nexsys::algos::{Variable, Settings, Multistart};
+/// use nexsys::algos::multistart;
+/// use nexsys::expr::Expr;
+/// 
+/// // a circle and a line cross twice
+/// let my_sys = vec![
+///     Expr::parse("x^2 + y^2 - 4").unwrap(), 
+///     Expr::parse("y - x").unwrap()
+/// ];
+/// let guess = HashMap::from([
+///     ("x", Variable::new(1.0, Some([-5.0, 5.0]))),
+///     ("y", Variable::new(1.0, Some([-5.0, 5.0])))
+/// ]);
+/// 
+/// let found = multistart(&my_sys, guess, &Multistart::default(), &Settings::default()).unwrap().unwrap();
+/// 
+/// assert_eq!(found.len(), 2);
+/// ```
+pub fn multistart<'a>(system: &[Expr], guess: HashMap<&'a str, Variable>, options: &Multistart, settings: &Settings)
+-> Result<Report<Solutions<'a>>, Box<dyn Error>> {
+
+    let mut keys: Vec<&str> = guess.keys().copied().collect();
+    keys.sort(); // keep the samples repeatable
+
+    let mut domains = vec![];
+    for k in &keys {
+        match guess[k].get_domain() {
+            Some(d) => domains.push(d),
+            None => return Err(Box::new(DomainRequiredError(k.to_string())))
+        }
+    }
+    let vars: Vec<(&str, f64)> = keys.iter()
+        .zip(&domains)
+        .map(|(&k, [lo, hi])| (k, (hi - lo).max(f64::EPSILON)))
+        .collect();
+
+    let mut starts = vec![guess.clone()];
+    for p in sample(options.starts.saturating_sub(1), &domains, options) {
+        let mut start = guess.clone();
+        for (k, x) in keys.iter().zip(p) {
+            if let Some(v) = start.get_mut(k) {
+                v.change(x);
+            }
+        }
+        starts.push(start);
+    }
+
+    let mut roots: Solutions<'a> = vec![];
+    let mut iterations = 0;
+    let mut residual: f64 = 0.0;
+
+    for start in starts {
+        loop {
+            let deflated = deflate(system, &roots, &vars);
+            let ans = match mv_newton_raphson(&deflated, start.clone(), settings) {
+                Ok(ans) => ans,
+                Err(e) if is_interruption(e.as_ref()) => return Err(e),
+                Err(_) => break
+            };
+            iterations += ans.iterations;
+
+            let x = match ans.solution {
+                Solution::Converged(x) => x,
+                Solution::NonConverged(_) => break
+            };
+
+            let fx: f64 = residuals(system, &x)?.iter().map(|i| i.abs()).sum();
+            let new = roots.iter().all(|r| vars.iter().any(
+                |&(v, width)| (r[v].as_f64() - x[v].as_f64()).abs() > 1E-6 * width
+            ));
+
+            if fx > settings.tolerance || !new {
+                break
+            }
+            residual = residual.max(fx);
+            roots.push(x);
+        }
+    }
+
+    if roots.is_empty() {
+        Ok(Report { solution: Solution::NonConverged(roots), iterations, residual, undetermined: vec![], criteria: None, history: vec![] })
+    } else {
+        Ok(Report { solution: Solution::Converged(roots), iterations, residual, undetermined: vec![], criteria: None, history: vec![] })
+    }
+}
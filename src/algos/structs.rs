@@ -1,5 +1,8 @@
-use std::collections::HashMap;
-use crate::parsing::legal_variable;
+use std::{collections::HashMap, error::Error};
+use serde::{Deserialize, Serialize};
+use crate::expr::{Expr, Op};
+use super::{Convergence, Multistart};
+use crate::monitor::Monitor;
 
 /// Effectively an `f64`, but with an optional domain that the value must be on.
 #[derive(Clone)]
@@ -71,6 +74,18 @@ impl Variable {
     pub fn get_domain(&self) -> Option<[f64; 2]> {
         self.domain
     }
+
+    /// Returns `true` if `self.value` is on (or within rounding error of) either end of `self.domain`.
+    /// A solution with a variable on a bound is usually pinned there, rather than truly solved.
+    pub fn at_bound(&self) -> bool {
+        match self.domain {
+            Some([lo, hi]) => {
+                let slack = 1E-9 * (hi - lo).abs().max(f64::MIN_POSITIVE);
+                (self.value - lo).abs() <= slack || (self.value - hi).abs() <= slack
+            },
+            None => false
+        }
+    }
 }
 
 /// Represents an equation and gives info about its known and unknown variables
@@ -79,19 +94,35 @@ impl Variable {
 #[derive(PartialEq)]
 pub struct Equation {
     text: String,
+    expr: Expr,
     vars: Vec<String>,
     n: usize
 }
 impl Equation {
-    /// Initializes a new `Equation` struct
-    pub fn new(text: &str) -> Equation {
-        let mut vars = legal_variable(text);
+    /// Initializes a new `Equation` struct, parsing the text into an expression 
+    /// that evaluates to 0 when the equation holds.
+    pub fn new(text: &str) -> Result<Equation, Box<dyn Error>> {
+        let expr = match text.split_once('=') {
+            Some((lhs, rhs)) => Expr::Bin(
+                Op::Sub, 
+                Box::new(Expr::parse(lhs)?), 
+                Box::new(Expr::parse(rhs)?)
+            ),
+            None => Expr::parse(text)?
+        };
+
+        let mut vars = expr.vars();
 
         vars.sort();
 
         let n = vars.len();
 
-        Equation { text: text.to_string(), vars, n }
+        Ok(Equation { text: text.to_string(), expr, vars, n })
+    }
+
+    /// Returns the parsed expression that evaluates to 0 when the equation holds.
+    pub fn expr(&self) -> &Expr {
+        &self.expr
     }
 
     /// Returns the equation as an expression that evaluates to 0 when the system is solved.
@@ -150,6 +181,9 @@ impl Equation {
 #[derive(Debug)]
 #[derive(PartialEq)]
 /// A block manager object for identifying constrained systems of equations.
+/// 
+/// Only equations with identical sets of unknowns are grouped together. 
+/// `Incidence` should be preferred for finding coupled systems.
 pub struct BlockMgr<'a> {
     /// blocks i, j
     /// 
@@ -211,4 +245,107 @@ impl <'a> BlockMgr<'a> {
             Some(eqns)
         }
     }
-}
\ No newline at end of file
+}
+/// How the solving algorithms find derivatives.
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Derivative {
+    /// Exact derivatives found by evaluating equations with dual numbers.
+    Analytic,
+    /// Derivatives approximated with a one-sided finite difference.
+    FiniteDifference
+}
+
+/// How the Jacobian is kept up to date between iterations when solving systems of equations.
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JacobianUpdate {
+    /// The Jacobian is evaluated every iteration. (Newton's method)
+    Full,
+    /// The inverse Jacobian is corrected with Broyden's "good" update.
+    GoodBroyden,
+    /// The inverse Jacobian is corrected with Broyden's "bad" update.
+    BadBroyden
+}
+
+/// Settings that control the behavior of the solving algorithms.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// The residual that an algorithm must reach to converge.
+    pub tolerance: f64,
+    /// The number of iterations an algorithm may take before giving up.
+    pub max_iterations: usize,
+    /// How derivatives and Jacobians are found.
+    pub derivative: Derivative,
+    /// Whether Newton steps are shortened by a backtracking line search 
+    /// when a full step would not reduce the residual enough.
+    pub line_search: bool,
+    /// The largest change allowed in each variable per Newton step. Steps 
+    /// that would exceed a cap are shortened without changing direction.
+    pub max_step: HashMap<String, f64>,
+    /// Blocks with more unknowns than this use sparse Jacobians and sparse 
+    /// linear solves, which are much faster when each equation only uses a 
+    /// few of the block's unknowns.
+    pub sparse_threshold: usize,
+    /// How the Jacobian is kept up to date when solving systems of equations.
+    pub update: JacobianUpdate,
+    /// If given, blocks whose unknowns all have domains are searched for every
+    /// solution, and the first solution found is used.
+    pub multistart: Option<Multistart>,
+    /// Whether Newton's method scales the unknowns by their typical magnitudes 
+    /// and each equation by the typical size of its terms. This makes the 
+    /// linear solves, line search and convergence test independent of the units 
+    /// used, so the tolerance applies to the scaled residuals. Off by default, so 
+    /// the tolerance applies to the absolute residuals unless this is turned on.
+    pub scaling: bool,
+    /// The typical magnitude of each variable, which is used when `scaling` is on. 
+    /// Variables that aren't given here are scaled by their guess value, or by their 
+    /// domain if the guess is zero.
+    pub typical: HashMap<String, f64>,
+    /// The criteria used to decide when Newton's method, Broyden's method and the
+    /// Levenberg-Marquardt method have converged, in addition to `tolerance`.
+    pub convergence: Convergence,
+    /// Whether Newton's method records the state after each iteration (see `Report::history`).
+    /// Finding the condition of each Jacobian makes this slower, so it is off by default.
+    pub history: bool,
+    /// The observer, cancellation token and time limit of the solve, which are
+    /// checked between iterations. Not part of the serialized settings.
+    #[serde(skip)]
+    pub monitor: Monitor
+}
+impl Settings {
+    /// Initializes `Settings` with the given tolerance and iteration limit, 
+    /// using the defaults for everything else.
+    pub fn new(tolerance: f64, max_iterations: usize) -> Settings {
+        Settings { tolerance, max_iterations, ..Settings::default() }
+    }
+}
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            tolerance: 1E-10,
+            max_iterations: 300,
+            derivative: Derivative::Analytic,
+            line_search: true,
+            max_step: HashMap::new(),
+            sparse_threshold: 50,
+            update: JacobianUpdate::Full,
+            multistart: None,
+            scaling: false,
+            typical: HashMap::new(),
+            convergence: Convergence::default(),
+            history: false,
+            monitor: Monitor::default()
+        }
+    }
+}
@@ -1,34 +1,35 @@
-#[cfg(feature = "c_ffi")] 
-use std::ffi::{CStr, CString, c_char, c_int, c_float};
-use crate::solve;
+#[cfg(feature = "c_ffi")]
+use std::ffi::{CStr, CString, c_char, c_double};
+use std::error::Error;
+use crate::{solve, build, config::SolverConfig, solver::Nexsys, SolverOutput};
 
-/// Nexsys solver function exposed to C/C++
-/// For ease of use, solution 
-#[no_mangle]
-pub extern "C" fn c_solve(
-    system: *const c_char, 
-    tolerance: c_float, 
-    max_iterations: c_int, 
-    allow_nonconvergence: bool
-) {
-
-    let sys = unsafe {
-        String::from_utf8_lossy(
-            CStr::from_ptr(system).to_bytes())
-            .to_string()
-    };
+/// Reads a C string into a Rust `String`.
+///
+/// # Safety
+/// `text` must be a valid, null-terminated C string.
+unsafe fn read_c_str(text: *const c_char) -> String {
+    String::from_utf8_lossy(
+        CStr::from_ptr(text).to_bytes())
+        .to_string()
+}
 
-    match solve(
-        &sys, 
-        Some(tolerance as f64), 
-        Some(max_iterations as usize), 
-        allow_nonconvergence
-    ) {
-        Err(e) => {
-            CString::new(format!("{e}"))
-                .expect("rust error: failed to format error as CString")
-                .as_ptr();
-        },
+/// Reads solver options from a C string of JSON (see `SolverConfig`), 
+/// using the default options if `config` is null.
+///
+/// # Safety
+/// `config` must be null or a valid, null-terminated C string.
+unsafe fn read_config(config: *const c_char) -> Result<SolverConfig, Box<dyn Error>> {
+    if config.is_null() {
+        return Ok(SolverConfig::default())
+    }
+    Ok(serde_json::from_str(&read_c_str(config))?)
+}
+
+/// Formats the result of a solve as a C string. Solutions are given as
+/// newline-separated `name=value` pairs, and errors are given as their message.
+fn format_result(res: Result<SolverOutput, Box<dyn Error>>) -> *mut c_char {
+    let ans = match res {
+        Err(e) => format!("{e}"),
         Ok(o) => {
             let (soln, _) = o;
             let mut ans = String::new();
@@ -36,8 +37,171 @@ pub extern "C" fn c_solve(
             for (k, v) in soln {
                 ans += &format!("{k}={}\n", v.as_f64());
             }
-
-            CString::new(ans).expect("rust error: failed to format solution as CString")
+            ans
         }
-    }    
-}
\ No newline at end of file
+    };
+
+    CString::new(ans)
+        .expect("rust error: failed to format solution as CString")
+        .into_raw()
+}
+
+/// Nexsys solver function exposed to C/C++
+/// For ease of use, the solution is returned as a string of `name=value`
+/// lines. The solver options are given as a JSON object (e.g. `{"tolerance": 1e-8}`),
+/// or as a null pointer to use the defaults. The returned string must be freed 
+/// with `nexsys_free_string`.
+///
+/// # Safety
+/// `system` must be a valid, null-terminated C string, and `config` must be 
+/// null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn c_solve(system: *const c_char, config: *const c_char) -> *mut c_char {
+
+    let sys = read_c_str(system);
+
+    format_result(read_config(config).and_then(|c| solve(&sys, &c)))
+}
+
+/// Compiles Nexsys code into a reusable solver, with options given the same way as 
+/// for `c_solve`. Returns a null pointer if the code or options fail to compile. 
+/// The solver must be freed with `nexsys_free`.
+///
+/// # Safety
+/// `system` must be a valid, null-terminated C string, and `config` must be 
+/// null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn nexsys_new(system: *const c_char, config: *const c_char) -> *mut Nexsys {
+
+    let sys = read_c_str(system);
+
+    match read_config(config).and_then(|c| build(&sys, &c)) {
+        Ok(n) => Box::into_raw(Box::new(n)),
+        Err(_) => std::ptr::null_mut()
+    }
+}
+
+/// Manually specifies the value of a variable in a solver created by `nexsys_new`.
+///
+/// # Safety
+/// `nexsys` must be a pointer returned by `nexsys_new` and `var` must be a
+/// valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn nexsys_edit(nexsys: *mut Nexsys, var: *const c_char, value: c_double) {
+    if let Some(n) = nexsys.as_mut() {
+        n.edit(&read_c_str(var), value);
+    }
+}
+
+/// Specifies an initial guess value for a variable in a solver created by `nexsys_new`.
+///
+/// # Safety
+/// `nexsys` must be a pointer returned by `nexsys_new` and `var` must be a
+/// valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn nexsys_guess(nexsys: *mut Nexsys, var: *const c_char, value: c_double) {
+    if let Some(n) = nexsys.as_mut() {
+        n.guess(&read_c_str(var), value);
+    }
+}
+
+/// Removes a value that was previously given by `nexsys_edit`.
+///
+/// # Safety
+/// `nexsys` must be a pointer returned by `nexsys_new` and `var` must be a
+/// valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn nexsys_remove_edit(nexsys: *mut Nexsys, var: *const c_char) {
+    if let Some(n) = nexsys.as_mut() {
+        n.remove_edit(&read_c_str(var));
+    }
+}
+
+/// Adds a domain of `[lo, hi]` for a variable in a solver created by `nexsys_new`.
+///
+/// # Safety
+/// `nexsys` must be a pointer returned by `nexsys_new` and `var` must be a
+/// valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn nexsys_domain(nexsys: *mut Nexsys, var: *const c_char, lo: c_double, hi: c_double) {
+    if let Some(n) = nexsys.as_mut() {
+        n.domain(&read_c_str(var), [lo, hi]);
+    }
+}
+
+/// Specifies the tolerance on the residual of the `i`th equation of a solver created by `nexsys_new`.
+///
+/// # Safety
+/// `nexsys` must be a pointer returned by `nexsys_new`.
+#[no_mangle]
+pub unsafe extern "C" fn nexsys_equation_tolerance(nexsys: *mut Nexsys, i: usize, tolerance: c_double) {
+    if let Some(n) = nexsys.as_mut() {
+        n.equation_tolerance(i, tolerance);
+    }
+}
+
+/// Specifies the tolerance on the step of a variable in a solver created by `nexsys_new`.
+///
+/// # Safety
+/// `nexsys` must be a pointer returned by `nexsys_new` and `var` must be a
+/// valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn nexsys_step_tolerance(nexsys: *mut Nexsys, var: *const c_char, tolerance: c_double) {
+    if let Some(n) = nexsys.as_mut() {
+        n.step_tolerance(&read_c_str(var), tolerance);
+    }
+}
+
+/// Replaces the options of a solver created by `nexsys_new`, given the same way as 
+/// for `c_solve`. Returns `false` and leaves the solver unchanged if the options 
+/// can't be read.
+///
+/// # Safety
+/// `nexsys` must be a pointer returned by `nexsys_new`, and `config` must be 
+/// null or a valid, null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn nexsys_configure(nexsys: *mut Nexsys, config: *const c_char) -> bool {
+    match (nexsys.as_mut(), read_config(config)) {
+        (Some(n), Ok(c)) => {
+            n.set_config(c);
+            true
+        },
+        _ => false
+    }
+}
+
+/// Solves a solver created by `nexsys_new`. The solver is left intact so that it
+/// can be edited and solved again. The returned string has the same format as
+/// the one returned by `c_solve` and must be freed with `nexsys_free_string`.
+///
+/// # Safety
+/// `nexsys` must be a pointer returned by `nexsys_new`.
+#[no_mangle]
+pub unsafe extern "C" fn nexsys_solve(nexsys: *mut Nexsys) -> *mut c_char {
+    match nexsys.as_mut() {
+        Some(n) => format_result(n.solve()),
+        None => std::ptr::null_mut()
+    }
+}
+
+/// Frees a solver created by `nexsys_new`.
+///
+/// # Safety
+/// `nexsys` must be a pointer returned by `nexsys_new` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn nexsys_free(nexsys: *mut Nexsys) {
+    if !nexsys.is_null() {
+        drop(Box::from_raw(nexsys));
+    }
+}
+
+/// Frees a string returned by `c_solve` or `nexsys_solve`.
+///
+/// # Safety
+/// `text` must be a pointer returned by `c_solve` or `nexsys_solve` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn nexsys_free_string(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
@@ -0,0 +1,219 @@
+use std::{sync::Arc, time::Duration};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use crate::{
+    algos::{Convergence, Derivative, JacobianUpdate, Method, Multistart, Settings},
+    errors::TimeLimitError,
+    monitor::{CancellationToken, Observer}
+};
+
+/// The options that control how Nexsys solves a system.
+///
+/// A `SolverConfig` is usually made with `SolverConfig::builder()`, but it can also
+/// be read from JSON (or any other format supported by `serde`), or from the `options`
+/// block of Nexsys code (see `parsing::options`). Options that aren't given keep
+/// their default values.
+/// # Example
+/// ```
+/// use nexsys::config::SolverConfig;
+///
+/// let config: SolverConfig = serde_json::from_str(r#"{"tolerance": 1e-8, "fallbacks": ["brent"]}"#).unwrap();
+///
+/// assert_eq!(config.settings.tolerance, 1e-8);
+/// assert_eq!(config.settings.max_iterations, 300);
+/// ```
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct SolverConfig {
+    /// The settings used by the solving algorithms.
+    #[serde(flatten)]
+    pub settings: Settings,
+    /// The methods tried, in order, when the first method fails to converge. Single
+    /// equations can fall back to `Method::Brent`, and systems of equations can fall
+    /// back to `Method::Homotopy`, `Method::LevenbergMarquardt`, `Method::MvNewton`
+    /// or `Method::Broyden`. Other methods are ignored.
+    pub fallbacks: Vec<Method>,
+    /// Whether solutions that didn't converge are kept (and reported as such in the
+    /// log) instead of returning an error.
+    pub allow_nonconvergence: bool,
+    /// The initial guess used for variables that weren't given one.
+    pub default_guess: f64,
+    /// If given, the number of seconds a whole solve may take before it stops with a
+    /// `SolverTimeoutError` that carries the partial solution. Limits that are negative
+    /// or not finite are rejected with a `TimeLimitError`, and limits too long to be
+    /// measured are treated as no limit.
+    #[serde(deserialize_with = "time_limit")]
+    pub time_limit: Option<f64>
+}
+impl Default for SolverConfig {
+    fn default() -> SolverConfig {
+        SolverConfig {
+            settings: Settings::default(),
+            fallbacks: vec![Method::Brent, Method::Homotopy, Method::LevenbergMarquardt],
+            allow_nonconvergence: false,
+            default_guess: 1.0,
+            time_limit: None
+        }
+    }
+}
+impl SolverConfig {
+    /// Returns a builder that starts from the default options.
+    pub fn builder() -> SolverConfigBuilder {
+        SolverConfigBuilder::default()
+    }
+}
+
+/// Reads a time limit, rejecting any that `SolverConfig::time_limit` can't use.
+fn time_limit<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    match Option::<f64>::deserialize(deserializer)? {
+        Some(limit) if !limit.is_finite() || limit < 0.0 => Err(de::Error::custom(TimeLimitError(limit))),
+        limit => Ok(limit)
+    }
+}
+
+/// Builds a `SolverConfig` one option at a time, starting from the defaults.
+/// # Example
+/// ```
+/// use nexsys::config::SolverConfig;
+/// use nexsys::algos::{JacobianUpdate, Method};
+///
+/// let config = SolverConfig::builder()
+///     .tolerance(1e-8)
+///     .max_iterations(50)
+///     .update(JacobianUpdate::GoodBroyden)
+///     .fallbacks(vec![Method::LevenbergMarquardt])
+///     .allow_nonconvergence(true)
+///     .build();
+///
+/// assert_eq!(config.settings.max_iterations, 50);
+/// assert!(config.allow_nonconvergence);
+/// ```
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(Default)]
+pub struct SolverConfigBuilder {
+    config: SolverConfig
+}
+impl SolverConfigBuilder {
+    /// Sets the residual that the algorithms must reach to converge.
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.config.settings.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the number of iterations an algorithm may take before giving up.
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.config.settings.max_iterations = max_iterations;
+        self
+    }
+
+    /// Sets how derivatives and Jacobians are found.
+    pub fn derivative(mut self, derivative: Derivative) -> Self {
+        self.config.settings.derivative = derivative;
+        self
+    }
+
+    /// Sets how systems of equations are solved first: Newton's method
+    /// (`JacobianUpdate::Full`) or one of Broyden's methods.
+    pub fn update(mut self, update: JacobianUpdate) -> Self {
+        self.config.settings.update = update;
+        self
+    }
+
+    /// Sets whether steps are damped by a backtracking line search.
+    pub fn line_search(mut self, line_search: bool) -> Self {
+        self.config.settings.line_search = line_search;
+        self
+    }
+
+    /// Limits how far the given variable may move in a single step.
+    pub fn max_step(mut self, var: &str, cap: f64) -> Self {
+        self.config.settings.max_step.insert(var.to_string(), cap);
+        self
+    }
+
+    /// Sets the number of unknowns above which blocks are solved with sparse Jacobians.
+    pub fn sparse_threshold(mut self, sparse_threshold: usize) -> Self {
+        self.config.settings.sparse_threshold = sparse_threshold;
+        self
+    }
+
+    /// Searches blocks of equations for every solution with the given options.
+    pub fn multistart(mut self, multistart: Multistart) -> Self {
+        self.config.settings.multistart = Some(multistart);
+        self
+    }
+
+    /// Sets whether unknowns and equations are scaled during Newton iterations.
+    pub fn scaling(mut self, scaling: bool) -> Self {
+        self.config.settings.scaling = scaling;
+        self
+    }
+
+    /// Sets the typical magnitude of the given variable, which is used to scale it.
+    pub fn typical(mut self, var: &str, magnitude: f64) -> Self {
+        self.config.settings.typical.insert(var.to_string(), magnitude);
+        self
+    }
+
+    /// Sets the criteria used to decide when the algorithms have converged.
+    pub fn convergence(mut self, convergence: Convergence) -> Self {
+        self.config.settings.convergence = convergence;
+        self
+    }
+
+    /// Replaces all of the settings used by the solving algorithms at once.
+    pub fn settings(mut self, settings: Settings) -> Self {
+        self.config.settings = settings;
+        self
+    }
+
+    /// Sets the methods tried, in order, when the first method fails to converge.
+    pub fn fallbacks(mut self, fallbacks: Vec<Method>) -> Self {
+        self.config.fallbacks = fallbacks;
+        self
+    }
+
+    /// Sets whether solutions that didn't converge are kept instead of returning an error.
+    pub fn allow_nonconvergence(mut self, allow_nonconvergence: bool) -> Self {
+        self.config.allow_nonconvergence = allow_nonconvergence;
+        self
+    }
+
+    /// Sets the initial guess used for variables that weren't given one.
+    pub fn default_guess(mut self, default_guess: f64) -> Self {
+        self.config.default_guess = default_guess;
+        self
+    }
+
+    /// Sets whether Newton's method records the state after each iteration.
+    pub fn history(mut self, history: bool) -> Self {
+        self.config.settings.history = history;
+        self
+    }
+
+    /// Limits how long a whole solve may take.
+    pub fn time_limit(mut self, limit: Duration) -> Self {
+        self.config.time_limit = Some(limit.as_secs_f64());
+        self
+    }
+
+    /// Sends the events of the solve to `observer`.
+    pub fn observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.config.settings.monitor = self.config.settings.monitor.observer(observer);
+        self
+    }
+
+    /// Stops the solve between iterations once `token` is cancelled.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.config.settings.monitor = self.config.settings.monitor.cancellation(token);
+        self
+    }
+
+    /// Returns the finished `SolverConfig`.
+    pub fn build(self) -> SolverConfig {
+        self.config
+    }
+}
@@ -1,4 +1,5 @@
-use std::{error::Error, fmt::{self, Display}};
+use std::{collections::HashMap, error::Error, fmt::{self, Display}, time::Duration};
+use crate::{algos::Variable, solver::SolveStep};
 
 /// More concise syntax for implementing `Error` and `Display` for both structs and enums
 macro_rules! impl_err {
@@ -103,9 +104,212 @@ impl_err!(
     "failed to identify conversion factors"
 );
 
+/// Error type for algorithms that did not converge. When returned by `Nexsys`, carries 
+/// the log of the steps taken so far, ending with the one that didn't converge.
 #[derive(Debug)]
-pub struct SolverConvergenceError;
+#[derive(Default)]
+pub struct SolverConvergenceError {
+    /// The steps taken before the solver stopped (see `Settings::history`).
+    pub log: Vec<SolveStep>
+}
 impl_err!(
     SolverConvergenceError,
     "solver algorithm did not converge. consider allowing non-convergent solutions, or try to remove discontinuities from your system"
-);
\ No newline at end of file
+);
+
+/// Error type for systems that still have unknowns left after the solver runs out of 
+/// equations it can solve. Carries a diagnosis of the remaining equations as well as 
+/// the partial solution that was found.
+#[derive(Debug)]
+pub struct SolverConstraintError {
+    /// Variables that could not be solved for.
+    pub unsolved: Vec<String>,
+    /// Equations that still contain unknowns.
+    pub unused: Vec<String>,
+    /// Groups of equations that contain more equations than unknowns.
+    pub overdetermined: Vec<Vec<String>>,
+    /// The number of unknowns minus the number of equations left in the system.
+    pub degrees_of_freedom: isize,
+    /// Variables that, if specified, would make the remaining system properly constrained.
+    pub suggestions: Vec<String>,
+    /// The values that were solved for before the solver stalled.
+    pub solution: HashMap<String, Variable>,
+    /// The steps taken before the solver stalled.
+    pub log: Vec<SolveStep>
+}
+impl Error for SolverConstraintError {}
+impl Display for SolverConstraintError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, 
+            "system is not properly constrained ({} degrees of freedom)\nunsolved variables: {}\nunused equations:\n", 
+            self.degrees_of_freedom, 
+            self.unsolved.join(", ")
+        )?;
+
+        for eqn in &self.unused {
+            writeln!(f, "    {eqn}")?;
+        }
+
+        for grp in &self.overdetermined {
+            writeln!(f, "over-determined equations:")?;
+            for eqn in grp {
+                writeln!(f, "    {eqn}")?;
+            }
+        }
+
+        if !self.suggestions.is_empty() {
+            write!(f, "consider specifying: {}", self.suggestions.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct SweepFormatError;
+impl_err!(
+    SweepFormatError,
+    "sweep failed to compile. each case must give a value for every swept variable"
+);
+
+/// Error type for expressions that could not be parsed.
+#[derive(Debug)]
+pub struct ExprParseError {
+    pub expr: String,
+    pub reason: &'static str
+}
+impl Error for ExprParseError {}
+impl Display for ExprParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to parse `{}`: {}", self.expr, self.reason)
+    }
+}
+
+/// Error type for evaluating an expression that contains a variable with no value.
+#[derive(Debug)]
+pub struct UnknownVariableError(pub String);
+impl Error for UnknownVariableError {}
+impl Display for UnknownVariableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "variable `{}` has no value", self.0)
+    }
+}
+
+/// Error type for a bracketing root finder that could not find a sign change in an equation.
+#[derive(Debug)]
+pub struct NoSignChangeError(pub String);
+impl Error for NoSignChangeError {}
+impl Display for NoSignChangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` does not change sign anywhere it was searched, so it may not have a root", self.0)
+    }
+}
+
+/// Error type for an algorithm that needs a variable to have a domain, but was given one without a domain.
+#[derive(Debug)]
+pub struct DomainRequiredError(pub String);
+impl Error for DomainRequiredError {}
+impl Display for DomainRequiredError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a domain must be given for `{0}` (e.g. `keep {0} on [0, 10]`) to search it for roots", self.0)
+    }
+}
+
+/// Error type for factoring a singular matrix. Contains the variables whose 
+/// columns are linearly dependent on the columns before them.
+#[derive(Debug)]
+pub struct SingularMatrixError {
+    pub vars: Vec<String>
+}
+impl Error for SingularMatrixError {}
+impl Display for SingularMatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, 
+            "matrix is singular: the columns for {} depend on the other columns. check that the equations are independent and that these variables appear in them", 
+            self.vars.iter().map(|i| format!("`{i}`")).collect::<Vec<String>>().join(", ")
+        )
+    }
+}
+
+/// Error type for tracing a branch of solutions with a parameter that isn't the 
+/// only variable left free by the equations.
+#[derive(Debug)]
+pub struct ContinuationError(pub String);
+impl Error for ContinuationError {}
+impl Display for ContinuationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a branch can't be traced with `{0}`: the equations must have exactly one more unknown than equations, including `{0}`", self.0)
+    }
+}
+
+/// Error type for convergence criteria that refer to an equation that doesn't exist.
+#[derive(Debug)]
+pub struct ConvergenceFormatError(pub String);
+impl Error for ConvergenceFormatError {}
+impl Display for ConvergenceFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` failed to compile. equations are counted from 1", self.0)
+    }
+}
+
+/// Error type for lines in an `options` block that don't set a solver option.
+#[derive(Debug)]
+pub struct OptionsError(pub String);
+impl Error for OptionsError {}
+impl Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` is not a valid solver option. options are given as `name: value`", self.0)
+    }
+}
+
+/// Error type for sweeps that can't be expanded into a reasonable set of cases, 
+/// e.g. because their step isn't positive or they would make too many cases.
+#[derive(Debug)]
+pub struct SweepRangeError(pub String);
+impl Error for SweepRangeError {}
+impl Display for SweepRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "sweep failed to compile. {}", self.0)
+    }
+}
+
+/// Error type for solves that were stopped by a `CancellationToken`. 
+/// Carries the partial solution that was found before the solve stopped.
+#[derive(Debug)]
+pub struct SolverCancelledError {
+    /// The values that were solved for before the solve was cancelled.
+    pub solution: HashMap<String, Variable>
+}
+impl Error for SolverCancelledError {}
+impl Display for SolverCancelledError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "solve was cancelled after finding {} values", self.solution.len())
+    }
+}
+
+/// Error type for solves that ran out of time (see `SolverConfig::time_limit`). 
+/// Carries the partial solution that was found before the time ran out.
+#[derive(Debug)]
+pub struct SolverTimeoutError {
+    /// The time that the solve was allowed to take.
+    pub limit: Duration,
+    /// The values that were solved for before the time ran out.
+    pub solution: HashMap<String, Variable>
+}
+impl Error for SolverTimeoutError {}
+impl Display for SolverTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "solve timed out after {:?} with {} values found", self.limit, self.solution.len())
+    }
+}
+
+/// Error type for time limits that aren't a finite, non-negative number of seconds.
+#[derive(Debug)]
+pub struct TimeLimitError(pub f64);
+impl Error for TimeLimitError {}
+impl Display for TimeLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "time limit must be a finite, non-negative number of seconds, not {}", self.0)
+    }
+}
@@ -0,0 +1,533 @@
+mod parser;
+
+use std::{error::Error, fmt::{self, Display}, f64::consts::{E, PI}};
+use crate::{errors::UnknownVariableError, mvcalc::Dual};
+
+/// Binary operators that can appear in an `Expr`.
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow
+}
+impl Op {
+    /// Applies the operator to two values.
+    pub fn apply(&self, a: f64, b: f64) -> f64 {
+        match self {
+            Op::Add => a + b,
+            Op::Sub => a - b,
+            Op::Mul => a * b,
+            Op::Div => a / b,
+            Op::Rem => a % b,
+            Op::Pow => a.powf(b)
+        }
+    }
+
+    /// Applies the operator to two dual numbers.
+    pub fn apply_dual(&self, a: Dual, b: Dual) -> Dual {
+        match self {
+            Op::Add => a + b,
+            Op::Sub => a - b,
+            Op::Mul => a * b,
+            Op::Div => a / b,
+            Op::Rem => a % b,
+            Op::Pow => a.powf(b)
+        }
+    }
+
+    /// Returns the binding strength of the operator. Higher values bind more tightly.
+    fn precedence(&self) -> u8 {
+        match self {
+            Op::Add | Op::Sub           => 1,
+            Op::Mul | Op::Div | Op::Rem => 2,
+            Op::Pow                     => 4
+        }
+    }
+}
+impl Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Op::Add => write!(f, " + "),
+            Op::Sub => write!(f, " - "),
+            Op::Mul => write!(f, "*"),
+            Op::Div => write!(f, "/"),
+            Op::Rem => write!(f, "%"),
+            Op::Pow => write!(f, "^")
+        }
+    }
+}
+
+/// Comparison operators used in conditional expressions. The discriminants
+/// match the codes produced by `nexsys::parsing::conditionals`.
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(PartialEq, Eq)]
+pub enum Cmp {
+    Eq = 1,
+    Le = 2,
+    Ge = 3,
+    Lt = 4,
+    Gt = 5,
+    Ne = 6
+}
+impl Cmp {
+    /// Returns the comparison operator for a code produced by `nexsys::parsing::conditionals`.
+    pub fn from_code(code: f64) -> Cmp {
+        if code == 1.0 {
+            Cmp::Eq
+        } else if code == 2.0 {
+            Cmp::Le
+        } else if code == 3.0 {
+            Cmp::Ge
+        } else if code == 4.0 {
+            Cmp::Lt
+        } else if code == 5.0 {
+            Cmp::Gt
+        } else {
+            Cmp::Ne
+        }
+    }
+
+    /// Compares two values.
+    pub fn test(&self, a: f64, b: f64) -> bool {
+        match self {
+            Cmp::Eq => a == b,
+            Cmp::Le => a <= b,
+            Cmp::Ge => a >= b,
+            Cmp::Lt => a < b,
+            Cmp::Gt => a > b,
+            Cmp::Ne => a != b
+        }
+    }
+}
+
+/// Functions that can be called in an `Expr`.
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(PartialEq, Eq)]
+pub enum Func {
+    Sqrt, Exp, Ln, Abs,
+    Sin, Cos, Tan,
+    Asin, Acos, Atan,
+    Sinh, Cosh, Tanh,
+    Asinh, Acosh, Atanh,
+    Floor, Ceil, Round,
+    Signum, Atan2,
+    Max, Min
+}
+impl Func {
+    const NAMES: [(&'static str, Func); 23] = [
+        ("sqrt", Func::Sqrt),   ("exp", Func::Exp),     ("ln", Func::Ln),       ("abs", Func::Abs),
+        ("sin", Func::Sin),     ("cos", Func::Cos),     ("tan", Func::Tan),
+        ("asin", Func::Asin),   ("acos", Func::Acos),   ("atan", Func::Atan),
+        ("sinh", Func::Sinh),   ("cosh", Func::Cosh),   ("tanh", Func::Tanh),
+        ("asinh", Func::Asinh), ("acosh", Func::Acosh), ("atanh", Func::Atanh),
+        ("floor", Func::Floor), ("ceil", Func::Ceil),   ("round", Func::Round),
+        ("signum", Func::Signum), ("atan2", Func::Atan2),
+        ("max", Func::Max),     ("min", Func::Min)
+    ];
+
+    /// Returns the function with the given name, if it exists.
+    pub fn from_name(name: &str) -> Option<Func> {
+        Func::NAMES.iter().find(|i| i.0 == name).map(|i| i.1)
+    }
+
+    /// Returns the name of the function.
+    pub fn name(&self) -> &'static str {
+        Func::NAMES.iter().find(|i| i.1 == *self).unwrap().0 // every variant is in `NAMES`
+    }
+
+    /// Returns `true` if the function accepts `n` arguments.
+    pub fn accepts(&self, n: usize) -> bool {
+        match self {
+            Func::Max | Func::Min => n > 0,
+            Func::Atan2 => n == 2,
+            _ => n == 1
+        }
+    }
+
+    /// Applies the function to the given arguments.
+    pub fn apply(&self, args: &[f64]) -> f64 {
+        let x = args[0];
+        match self {
+            Func::Sqrt   => x.sqrt(),
+            Func::Exp    => x.exp(),
+            Func::Ln     => x.ln(),
+            Func::Abs    => x.abs(),
+            Func::Sin    => x.sin(),
+            Func::Cos    => x.cos(),
+            Func::Tan    => x.tan(),
+            Func::Asin   => x.asin(),
+            Func::Acos   => x.acos(),
+            Func::Atan   => x.atan(),
+            Func::Sinh   => x.sinh(),
+            Func::Cosh   => x.cosh(),
+            Func::Tanh   => x.tanh(),
+            Func::Asinh  => x.asinh(),
+            Func::Acosh  => x.acosh(),
+            Func::Atanh  => x.atanh(),
+            Func::Floor  => x.floor(),
+            Func::Ceil   => x.ceil(),
+            Func::Round  => x.round(),
+            Func::Signum => x.signum(),
+            Func::Atan2  => x.atan2(args[1]),
+            Func::Max    => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Func::Min    => args.iter().copied().fold(f64::INFINITY, f64::min)
+        }
+    }
+
+    /// Applies the function to the given dual number arguments.
+    pub fn apply_dual(&self, args: &[Dual]) -> Dual {
+        let x = args[0];
+        match self {
+            Func::Sqrt   => x.sqrt(),
+            Func::Exp    => x.exp(),
+            Func::Ln     => x.ln(),
+            Func::Abs    => x.abs(),
+            Func::Sin    => x.sin(),
+            Func::Cos    => x.cos(),
+            Func::Tan    => x.tan(),
+            Func::Asin   => x.asin(),
+            Func::Acos   => x.acos(),
+            Func::Atan   => x.atan(),
+            Func::Sinh   => x.sinh(),
+            Func::Cosh   => x.cosh(),
+            Func::Tanh   => x.tanh(),
+            Func::Asinh  => x.asinh(),
+            Func::Acosh  => x.acosh(),
+            Func::Atanh  => x.atanh(),
+            Func::Floor  => x.step(f64::floor),
+            Func::Ceil   => x.step(f64::ceil),
+            Func::Round  => x.step(f64::round),
+            Func::Signum => x.step(f64::signum),
+            Func::Atan2  => x.atan2(args[1]),
+            Func::Max    => args.iter().copied().fold(x, |a, b| if b.re > a.re { b } else { a }),
+            Func::Min    => args.iter().copied().fold(x, |a, b| if b.re < a.re { b } else { a })
+        }
+    }
+}
+
+/// A parsed mathematical expression.
+///
+/// Expressions are parsed once and can then be searched for variables,
+/// have known values substituted in, and be evaluated without touching
+/// the original text again.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Expr {
+    /// A number.
+    Num(f64),
+    /// A named variable (or one of the constants `pi` and `e`).
+    Var(String),
+    /// The negation of an expression.
+    Neg(Box<Expr>),
+    /// A binary operation.
+    Bin(Op, Box<Expr>, Box<Expr>),
+    /// A function call.
+    Call(Func, Vec<Expr>),
+    /// A conditional: `if(a, cmp, b, then, other)` evaluates to `then` if `a cmp b` holds, and to `other` otherwise.
+    If(Cmp, Box<Expr>, Box<Expr>, Box<Expr>, Box<Expr>)
+}
+impl Expr {
+    /// Parses an expression from a string.
+    /// # Example
+    /// ```
+    /// use nexsys::expr::Expr;
+    ///
+    /// let my_expr = Expr::parse("x^2 + max(x, 2) - 1").unwrap();
+    ///
+    /// assert_eq!(my_expr.vars(), vec!["x"]);
+    /// assert_eq!(my_expr.to_string(), "x^2 + max(x, 2) - 1");
+    /// ```
+    pub fn parse(text: &str) -> Result<Expr, Box<dyn Error>> {
+        parser::parse(text)
+    }
+
+    /// Returns the variables used in the expression in the order they first appear.
+    pub fn vars(&self) -> Vec<String> {
+        let mut res = vec![];
+        self.collect_vars(&mut res);
+        res
+    }
+
+    fn collect_vars(&self, res: &mut Vec<String>) {
+        match self {
+            Expr::Num(_) => (),
+            Expr::Var(v) => if !res.contains(v) {
+                res.push(v.to_string());
+            },
+            Expr::Neg(a) => a.collect_vars(res),
+            Expr::Bin(_, a, b) => {
+                a.collect_vars(res);
+                b.collect_vars(res);
+            },
+            Expr::Call(_, args) => for a in args {
+                a.collect_vars(res);
+            },
+            Expr::If(_, a, b, t, o) => for i in [a, b, t, o] {
+                i.collect_vars(res);
+            }
+        }
+    }
+
+    /// Returns `true` if the expression uses the given variable.
+    pub fn contains(&self, var: &str) -> bool {
+        match self {
+            Expr::Num(_) => false,
+            Expr::Var(v) => v == var,
+            Expr::Neg(a) => a.contains(var),
+            Expr::Bin(_, a, b) => a.contains(var) || b.contains(var),
+            Expr::Call(_, args) => args.iter().any(|i| i.contains(var)),
+            Expr::If(_, a, b, t, o) => [a, b, t, o].iter().any(|i| i.contains(var))
+        }
+    }
+
+    /// Returns a copy of the expression with every variable that `lookup` returns
+    /// a value for replaced by that value. Any part of the expression that no
+    /// longer contains variables is evaluated.
+    /// # Example
+    /// ```
+    /// use nexsys::expr::Expr;
+    ///
+    /// let my_expr = Expr::parse("x2 * exp(x) + e").unwrap();
+    ///
+    /// let subbed = my_expr.substitute(&|v| if v == "x" { Some(0.0) } else { None });
+    ///
+    /// assert_eq!(subbed.to_string(), "x2*1 + e");
+    /// ```
+    pub fn substitute(&self, lookup: &dyn Fn(&str) -> Option<f64>) -> Expr {
+        let res = match self {
+            Expr::Num(n) => Expr::Num(*n),
+            Expr::Var(v) => match lookup(v) {
+                Some(n) => Expr::Num(n),
+                None => Expr::Var(v.to_string())
+            },
+            Expr::Neg(a) => Expr::Neg(Box::new(a.substitute(lookup))),
+            Expr::Bin(op, a, b) => Expr::Bin(
+                *op,
+                Box::new(a.substitute(lookup)),
+                Box::new(b.substitute(lookup))
+            ),
+            Expr::Call(func, args) => Expr::Call(
+                *func,
+                args.iter().map(|i| i.substitute(lookup)).collect()
+            ),
+            Expr::If(cmp, a, b, t, o) => Expr::If(
+                *cmp,
+                Box::new(a.substitute(lookup)),
+                Box::new(b.substitute(lookup)),
+                Box::new(t.substitute(lookup)),
+                Box::new(o.substitute(lookup))
+            )
+        };
+        res.fold()
+    }
+
+    /// Evaluates the node if all of its children are numbers.
+    fn fold(self) -> Expr {
+        let constant = match &self {
+            Expr::Num(_) | Expr::Var(_) => false,
+            Expr::Neg(a) => a.is_num(),
+            Expr::Bin(_, a, b) => a.is_num() && b.is_num(),
+            Expr::Call(_, args) => args.iter().all(|i| i.is_num()),
+            Expr::If(_, a, b, t, o) => [a, b, t, o].iter().all(|i| i.is_num())
+        };
+
+        if constant {
+            if let Ok(n) = self.eval(&|_| None) {
+                return Expr::Num(n)
+            }
+        }
+        self
+    }
+
+    /// Returns `(a, b)` such that the expression is equal to `a*var + b`, or `None`
+    /// if the expression is not linear in `var`. Every other variable in the 
+    /// expression must be substituted beforehand.
+    /// # Example
+    /// ```
+    /// use nexsys::expr::Expr;
+    ///
+    /// let my_expr = Expr::parse("(3*x + 2)/4 - x").unwrap();
+    ///
+    /// assert_eq!(my_expr.linear("x"), Some((-0.25, 0.5)));
+    /// assert_eq!(Expr::parse("x*x").unwrap().linear("x"), None);
+    /// ```
+    pub fn linear(&self, var: &str) -> Option<(f64, f64)> {
+        if !self.contains(var) {
+            return self.eval(&|_| None).ok().map(|n| (0.0, n))
+        }
+
+        match self {
+            Expr::Var(_) => Some((1.0, 0.0)),
+            Expr::Neg(a) => a.linear(var).map(|(a, b)| (-a, -b)),
+            Expr::Bin(op, l, r) => {
+                let (la, lb) = l.linear(var)?;
+                let (ra, rb) = r.linear(var)?;
+                match op {
+                    Op::Add => Some((la + ra, lb + rb)),
+                    Op::Sub => Some((la - ra, lb - rb)),
+                    Op::Mul if la == 0.0 => Some((lb * ra, lb * rb)),
+                    Op::Mul if ra == 0.0 => Some((la * rb, lb * rb)),
+                    Op::Div if ra == 0.0 && rb != 0.0 => Some((la / rb, lb / rb)),
+                    Op::Pow if ra == 0.0 && rb == 1.0 => Some((la, lb)),
+                    _ => None
+                }
+            },
+            _ => None // functions and conditionals of `var` are not linear in general
+        }
+    }
+
+    /// Returns `true` if the expression is a number.
+    pub fn is_num(&self) -> bool {
+        matches!(self, Expr::Num(_))
+    }
+
+    /// Evaluates the expression, using `lookup` to find the values of variables.
+    /// The constants `pi` and `e` are used for variables of the same name that
+    /// `lookup` does not return a value for.
+    /// # Example
+    /// ```
+    /// use nexsys::expr::Expr;
+    ///
+    /// let my_expr = Expr::parse("if(x, 4, 0, -x, x) * 2").unwrap();
+    ///
+    /// let ans = my_expr.eval(&|v| if v == "x" { Some(-3.0) } else { None }).unwrap();
+    ///
+    /// assert_eq!(ans, 6.0);
+    /// ```
+    pub fn eval(&self, lookup: &dyn Fn(&str) -> Option<f64>) -> Result<f64, Box<dyn Error>> {
+        match self {
+            Expr::Num(n) => Ok(*n),
+            Expr::Var(v) => match lookup(v) {
+                Some(n) => Ok(n),
+                None => match v.as_str() {
+                    "pi" => Ok(PI),
+                    "e" => Ok(E),
+                    _ => Err(Box::new(UnknownVariableError(v.to_string())))
+                }
+            },
+            Expr::Neg(a) => Ok(-a.eval(lookup)?),
+            Expr::Bin(op, a, b) => Ok(op.apply(a.eval(lookup)?, b.eval(lookup)?)),
+            Expr::Call(func, args) => {
+                let vals = args.iter()
+                    .map(|i| i.eval(lookup))
+                    .collect::<Result<Vec<f64>, _>>()?;
+                Ok(func.apply(&vals))
+            },
+            Expr::If(cmp, a, b, t, o) => {
+                if cmp.test(a.eval(lookup)?, b.eval(lookup)?) {
+                    t.eval(lookup)
+                } else {
+                    o.eval(lookup)
+                }
+            }
+        }
+    }
+
+    /// Evaluates the expression with dual numbers, using `lookup` to find the values 
+    /// of variables. Seeding one variable with `Dual::var` gives the partial derivative 
+    /// of the expression with respect to that variable in the `du` part of the result.
+    /// # Example
+    /// ```
+    /// use nexsys::expr::Expr;
+    /// use nexsys::mvcalc::Dual;
+    ///
+    /// let my_expr = Expr::parse("x^2 * y + sin(y)").unwrap();
+    ///
+    /// let ans = my_expr.eval_dual(&|v| match v {
+    ///     "x" => Some(Dual::var(3.0)),
+    ///     "y" => Some(Dual::from(2.0)),
+    ///     _ => None
+    /// }).unwrap();
+    ///
+    /// assert_eq!(ans.re, 18.0 + 2_f64.sin());
+    /// assert_eq!(ans.du, 12.0);
+    /// ```
+    pub fn eval_dual(&self, lookup: &dyn Fn(&str) -> Option<Dual>) -> Result<Dual, Box<dyn Error>> {
+        match self {
+            Expr::Num(n) => Ok(Dual::from(*n)),
+            Expr::Var(v) => match lookup(v) {
+                Some(n) => Ok(n),
+                None => match v.as_str() {
+                    "pi" => Ok(Dual::from(PI)),
+                    "e" => Ok(Dual::from(E)),
+                    _ => Err(Box::new(UnknownVariableError(v.to_string())))
+                }
+            },
+            Expr::Neg(a) => Ok(-a.eval_dual(lookup)?),
+            Expr::Bin(op, a, b) => Ok(op.apply_dual(a.eval_dual(lookup)?, b.eval_dual(lookup)?)),
+            Expr::Call(func, args) => {
+                let vals = args.iter()
+                    .map(|i| i.eval_dual(lookup))
+                    .collect::<Result<Vec<Dual>, _>>()?;
+                Ok(func.apply_dual(&vals))
+            },
+            Expr::If(cmp, a, b, t, o) => {
+                if cmp.test(a.eval_dual(lookup)?.re, b.eval_dual(lookup)?.re) {
+                    t.eval_dual(lookup)
+                } else {
+                    o.eval_dual(lookup)
+                }
+            }
+        }
+    }
+
+    /// Returns the binding strength of the expression's outermost operation.
+    /// Used for deciding where parentheses are needed when formatting.
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Bin(op, _, _) => op.precedence(),
+            Expr::Neg(_) => 3,
+            Expr::Num(n) if n.is_sign_negative() => 3,
+            _ => 5
+        }
+    }
+}
+impl Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Num(n) => write!(f, "{n}"),
+            Expr::Var(v) => write!(f, "{v}"),
+            Expr::Neg(a) => {
+                if a.precedence() < 3 {
+                    write!(f, "-({a})")
+                } else {
+                    write!(f, "-{a}")
+                }
+            },
+            Expr::Bin(op, a, b) => {
+                let p = op.precedence();
+
+                // `^` is right-associative, everything else is left-associative
+                let left = a.precedence() < p || (*op == Op::Pow && a.precedence() == p);
+                let right = b.precedence() < p || (*op != Op::Pow && b.precedence() == p);
+
+                if left {
+                    write!(f, "({a})")?;
+                } else {
+                    write!(f, "{a}")?;
+                }
+                write!(f, "{op}")?;
+                if right {
+                    write!(f, "({b})")
+                } else {
+                    write!(f, "{b}")
+                }
+            },
+            Expr::Call(func, args) => write!(
+                f,
+                "{}({})",
+                func.name(),
+                args.iter().map(|i| i.to_string()).collect::<Vec<String>>().join(", ")
+            ),
+            Expr::If(cmp, a, b, t, o) => write!(f, "if({a}, {}, {b}, {t}, {o})", *cmp as u8)
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::{error::Error, iter::Peekable, str::Chars};
+use crate::{expr::{Expr, Op, Cmp, Func}, errors::ExprParseError};
+
+/// The tokens that make up an expression.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(char),
+    Open,
+    Close,
+    Comma
+}
+
+/// Splits an expression into tokens.
+fn tokenize(text: &str) -> Result<Vec<Token>, &'static str> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            tokens.push(Token::Num(number(&mut chars)?));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                ident.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Ident(ident));
+        } else {
+            tokens.push(match c {
+                '+' | '-' | '*' | '/' | '%' | '^' => Token::Op(c),
+                '(' => Token::Open,
+                ')' => Token::Close,
+                ',' => Token::Comma,
+                _ => return Err("unexpected character")
+            });
+            chars.next();
+        }
+    }
+    Ok(tokens)
+}
+
+/// Reads a number (e.g. `12`, `.5`, `1.2E-3`) from the front of `chars`.
+fn number(chars: &mut Peekable<Chars>) -> Result<f64, &'static str> {
+    let mut text = String::new();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() || c == '.' {
+            text.push(c);
+            chars.next();
+        } else if (c == 'e' || c == 'E') && !text.contains(['e', 'E']) {
+            // only treat `e` as an exponent if digits follow it
+            let mut ahead = chars.clone();
+            ahead.next();
+            let sign = matches!(ahead.peek(), Some('+') | Some('-'));
+            if sign {
+                ahead.next();
+            }
+            if !matches!(ahead.peek(), Some(d) if d.is_ascii_digit()) {
+                break;
+            }
+            text.push(c);
+            chars.next();
+            if sign {
+                text.push(chars.next().unwrap()); // checked above
+            }
+        } else {
+            break;
+        }
+    }
+
+    text.parse().map_err(|_| "invalid number")
+}
+
+/// Recursive descent parser over a list of tokens.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize
+}
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), &'static str> {
+        match self.next() {
+            Some(t) if t == token => Ok(()),
+            Some(_) => Err("unexpected token"),
+            None => Err("unexpected end of expression")
+        }
+    }
+
+    /// expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Expr, &'static str> {
+        let mut lhs = self.term()?;
+        while let Some(Token::Op(c)) = self.peek() {
+            let op = match c {
+                '+' => Op::Add,
+                '-' => Op::Sub,
+                _ => break
+            };
+            self.next();
+            lhs = Expr::Bin(op, Box::new(lhs), Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    /// term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<Expr, &'static str> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Op(c)) = self.peek() {
+            let op = match c {
+                '*' => Op::Mul,
+                '/' => Op::Div,
+                '%' => Op::Rem,
+                _ => break
+            };
+            self.next();
+            lhs = Expr::Bin(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    /// unary := ('-' | '+') unary | power
+    fn unary(&mut self) -> Result<Expr, &'static str> {
+        match self.peek() {
+            Some(Token::Op('-')) => {
+                self.next();
+                Ok(Expr::Neg(Box::new(self.unary()?)))
+            },
+            Some(Token::Op('+')) => {
+                self.next();
+                self.unary()
+            },
+            _ => self.power()
+        }
+    }
+
+    /// power := atom ('^' unary)?
+    fn power(&mut self) -> Result<Expr, &'static str> {
+        let base = self.atom()?;
+        if let Some(Token::Op('^')) = self.peek() {
+            self.next();
+            return Ok(Expr::Bin(Op::Pow, Box::new(base), Box::new(self.unary()?)))
+        }
+        Ok(base)
+    }
+
+    /// atom := number | ident | ident '(' args ')' | '(' expr ')'
+    fn atom(&mut self) -> Result<Expr, &'static str> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(Expr::Num(n)),
+            Some(Token::Open) => {
+                let inner = self.expr()?;
+                self.expect(Token::Close)?;
+                Ok(inner)
+            },
+            Some(Token::Ident(name)) => {
+                if self.peek() != Some(&Token::Open) {
+                    return Ok(Expr::Var(name))
+                }
+                self.next();
+                let args = self.args()?;
+                call(&name, args)
+            },
+            Some(_) => Err("unexpected token"),
+            None => Err("unexpected end of expression")
+        }
+    }
+
+    /// args := (expr (',' expr)*)? ')'
+    fn args(&mut self) -> Result<Vec<Expr>, &'static str> {
+        let mut args = vec![];
+        if self.peek() == Some(&Token::Close) {
+            self.next();
+            return Ok(args)
+        }
+        loop {
+            args.push(self.expr()?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::Close) => return Ok(args),
+                Some(_) => return Err("unexpected token"),
+                None => return Err("unexpected end of expression")
+            }
+        }
+    }
+}
+
+/// Builds a function call or conditional node.
+fn call(name: &str, mut args: Vec<Expr>) -> Result<Expr, &'static str> {
+    if name == "if" {
+        if args.len() != 5 {
+            return Err("conditionals take exactly 5 arguments")
+        }
+        let code = match args[1] {
+            Expr::Num(n) => n,
+            _ => return Err("conditional comparison operator must be a number")
+        };
+        let mut args = args.drain(..).map(Box::new);
+        let (a, _, b, t, o) = (
+            args.next().unwrap(), args.next().unwrap(), args.next().unwrap(),
+            args.next().unwrap(), args.next().unwrap()
+        ); // length was checked above
+        return Ok(Expr::If(Cmp::from_code(code), a, b, t, o))
+    }
+
+    match Func::from_name(name) {
+        Some(func) => if func.accepts(args.len()) {
+            Ok(Expr::Call(func, args))
+        } else {
+            Err("wrong number of arguments given to function")
+        },
+        None => Err("unknown function")
+    }
+}
+
+/// Parses an expression from a string.
+pub fn parse(text: &str) -> Result<Expr, Box<dyn Error>> {
+    let fail = |reason| ExprParseError { expr: text.trim().to_string(), reason };
+
+    let tokens = tokenize(text).map_err(fail)?;
+    let mut parser = Parser { tokens, pos: 0 };
+
+    let res = parser.expr().map_err(fail)?;
+
+    if parser.pos < parser.tokens.len() {
+        return Err(Box::new(fail("unexpected token")))
+    }
+    Ok(res)
+}
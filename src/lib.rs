@@ -1,5 +1,7 @@
 /// Provides implementations of single-variable and multivariate versions of Newton's method.
 pub mod algos;
+/// Provides a parsed representation of mathematical expressions.
+pub mod expr;
 /// Provides code for math operations that are useful in multivariate calculus.
 pub mod mvcalc;
 /// Provides access to the Nexsys equation solver engine. Useful for solving equations in other code.
@@ -10,6 +12,12 @@ pub mod units;
 pub mod parsing;
 /// Different errors specific to Nexsys implementations of algorithms.
 pub mod errors;
+/// Provides tools for solving a system across a table of input values.
+pub mod sweep;
+/// Provides the options that control how Nexsys solves a system.
+pub mod config;
+/// Provides tools for observing, cancelling and limiting the time of a solve while it runs.
+pub mod monitor;
 /// Not useful in Rust, but provides Python access to the Nexsys equation solving engine.
 #[cfg(feature = "python_ffi")]
 mod python_ffi;
@@ -20,36 +28,47 @@ mod c_ffi;
 
 use std::{collections::HashMap, error::Error};
 use algos::Variable;
-use solver::Nexsys;
-use parsing::{compile, domains, guess_values};
+use config::SolverConfig;
+use solver::{Nexsys, SolveStep};
+use parsing::{compile, convergence_criteria, domains, guess_values, options, root_choices, sweeps, typical_magnitudes};
+use sweep::{Table, grid};
 
 /// Shorthand for the contents of a Nexsys Solution: a
 /// `HashMap<String, Variable>` of variable values in the 
-/// solution as well as a `Vec<String>` of the steps taken
+/// solution as well as a `Vec<SolveStep>` of the steps taken
 /// to obtain the solution.
-type SolverOutput = (HashMap<String, Variable>, Vec<String>);
+type SolverOutput = (HashMap<String, Variable>, Vec<SolveStep>);
 
-/// Evaluates a string of nexsys-legal code and returns the 
-/// solution to the system as well as the steps taken to obtain it.
-pub fn solve(
-    system: &str, 
-    mut tolerance: Option<f64>, 
-    mut max_iterations: Option<usize>, 
-    allow_nonconvergence: bool
-) -> Result<SolverOutput, Box<dyn Error>> {
-
-    if tolerance        .is_none() { tolerance = Some(1E-10); }
-    if max_iterations   .is_none() { max_iterations = Some(300); }
-
-    let mut sys = Nexsys::new(
-        compile(system)?.as_str(), 
-        tolerance.unwrap(), 
-        max_iterations.unwrap(),
-        allow_nonconvergence
-    );
+/// Compiles a string of nexsys-legal code into a `Nexsys` solver, which 
+/// can be edited and solved as many times as needed. Options given in the 
+/// code's `options` block take precedence over those in `config`.
+pub fn build(system: &str, config: &SolverConfig) -> Result<Nexsys, Box<dyn Error>> {
+
+    let config = options(system, config)?;
+    let criteria = convergence_criteria(system, config.settings.convergence.clone())?;
+
+    let mut sys = Nexsys::new(compile(system)?.as_str(), config)?;
     
     sys.mass_add_domains(domains(system));
     sys.mass_add_guess(guess_values(system));
+    sys.mass_add_roots(root_choices(system)?);
+    sys.mass_add_typical(typical_magnitudes(system)?);
+    sys.convergence(criteria);
+
+    Ok(sys)
+}
+
+/// Evaluates a string of nexsys-legal code and returns the 
+/// solution to the system as well as the steps taken to obtain it.
+pub fn solve(system: &str, config: &SolverConfig) -> Result<SolverOutput, Box<dyn Error>> {
+    build(system, config)?.solve()
+}
 
-    sys.solve()
-}
\ No newline at end of file
+/// Evaluates a string of nexsys-legal code for every case of the sweeps 
+/// given in the code (e.g. `sweep x from 0 to 1 step 0.1`) and returns a
+/// table of the swept variables and the requested `outputs`. If no outputs 
+/// are given, every variable in the solution is reported.
+pub fn sweep(system: &str, outputs: &[&str], config: &SolverConfig) -> Result<Table, Box<dyn Error>> {
+    let cases = grid(&sweeps(system)?);
+    build(system, config)?.sweep(&cases, outputs)
+}
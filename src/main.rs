@@ -1,6 +1,13 @@
 use std::{env, process};
 use std::fs::{read_to_string, write};
-use nexsys::{solve, parsing::{conditionals, conversions, consts}};
+use nexsys::{
+    solve, build, 
+    config::SolverConfig,
+    solver::{history_table, SolveStep},
+    parsing::{conditionals, conversions, consts, sweeps}, 
+    sweep::{grid, cases_from_csv}, 
+    errors::{SolverConstraintError, SolverConvergenceError}
+};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -16,14 +23,22 @@ OPTIONS:
 --tolerance, -tol <float>              The tolerance that the solver should hit before returning a solution
 --max-iterations, -max <int>           The maximum number of iterations that the solver can take to converge
 --allow-nonconvergence, -ancv          Whether or not the solver should allow a solution to not converge
+--config, -cfg <filepath>              Reads the solver options from a .json file
 --output-file, -o                      Sends the results to a .txt file rather than printing them in the terminal
 --verbose -v                           Prints compiled nexsys code in the terminal for debugging
+--cases, -c <filepath>                 Solves the system for each case in a .csv file (first row names the variables)
+--outputs <var,var,...>                The variables to report in a sweep table (default: all)
+--format, -f <csv|md>                  The format of a sweep table (default: md)
+--history <filepath>                   Writes the state after each Newton iteration to a .csv file (not used for sweeps)
+
+Options given on the command line take precedence over those in an `options {{ ... }}` block 
+in the file, which take precedence over those in a config file.
 "#
         );
         process::exit(0);
     }
 
-    let system = match read_to_string(&args[1]) {
+    let mut system = match read_to_string(&args[1]) {
         Ok(o) => o,
         Err(_) => {
             println!("[nxc].....ERR: could not find the specified filepath");
@@ -31,17 +46,47 @@ OPTIONS:
         }
     };
 
-    let mut tolerance = None;
-    let mut max_iterations = None; 
-    let mut allow_nonconvergence = false;
+    let mut config = SolverConfig::default();
     let mut output_file = false; // todo: make this produce different file types
+    let mut cases = match sweeps(&system) {
+        Ok(o) => grid(&o),
+        Err(e) => {
+            println!("[nxc].....ERR: {e}");
+            process::exit(1);
+        }
+    };
+    let mut outputs = vec![];
+    let mut markdown = true;
+    let mut history = None;
+    let mut overrides = vec![]; // options set on the command line
+
+    // the config file is the starting point that every other option overrides
+    if let Some(i) = args.iter().position(|i| *i == "--config" || *i == "-cfg") {
+        let parsed = match read_to_string(value_of(&args, i)) {
+            Ok(o) => serde_json::from_str(&o),
+            Err(_) => {
+                println!("[nxc].....ERR: could not find the specified config file");
+                process::exit(1);
+            }
+        };
+        match parsed {
+            Ok(o) => {
+                println!("[nxc].....read solver options from {}", value_of(&args, i));
+                config = o;
+            },
+            Err(e) => {
+                println!("[nxc].....ERR: {e}");
+                process::exit(1);
+            }
+        }
+    }
 
     for i in 0..args.len() {
         if args[i] == *"--tolerance" || args[i] == *"-tol" {
-            match args[i+1].parse::<f64>() {
+            match value_of(&args, i).parse::<f64>() {
                 Ok(o) => {
                     println!("[nxc].....tolerance set to {o}");
-                    tolerance = Some(o);
+                    overrides.push(format!("tolerance: {o}"));
                 },
                 Err(_) => {
                     println!("[nxc].....ERR: tolerance is not a valid float value");
@@ -50,10 +95,10 @@ OPTIONS:
             }
         }
         if args[i] == *"--max-iterations" || args[i] == *"-max" {
-            match args[i+1].parse::<usize>() {
+            match value_of(&args, i).parse::<usize>() {
                 Ok(o) => {
                     println!("[nxc].....iteration limit set to: {o}");
-                    max_iterations = Some(o);
+                    overrides.push(format!("max_iterations: {o}"));
                 },
                 Err(_) => {
                     println!("[nxc].....ERR: iteration limit is not a valid integer value");
@@ -63,7 +108,7 @@ OPTIONS:
         }
         if args[i] == *"--allow-nonconvergence" || args[i] == *"-ancv" {
             println!("[nxc].....nonconvergence is allowed");
-            allow_nonconvergence = true;
+            overrides.push("allow_nonconvergence: true".to_string());
         }
         if args[i] == *"--verbose" || args[i] == *"-v" {
             println!("[nxc].....Printing compiled code...");
@@ -78,22 +123,120 @@ OPTIONS:
             println!("[nxc].....Writing to file...");
             output_file = true;
         }
+        if args[i] == *"--cases" || args[i] == *"-c" {
+            let parsed = match read_to_string(value_of(&args, i)) {
+                Ok(o) => cases_from_csv(&o),
+                Err(_) => {
+                    println!("[nxc].....ERR: could not find the specified cases file");
+                    process::exit(1);
+                }
+            };
+            match parsed {
+                Ok(o) => {
+                    println!("[nxc].....read {} cases from {}", o.len(), value_of(&args, i));
+                    cases = o;
+                },
+                Err(e) => {
+                    println!("[nxc].....ERR: {e}");
+                    process::exit(1);
+                }
+            }
+        }
+        if args[i] == *"--outputs" {
+            outputs = value_of(&args, i).split(',').map(|i| i.trim().to_string()).collect();
+        }
+        if args[i] == *"--history" {
+            history = Some(value_of(&args, i).to_string());
+            println!("[nxc].....recording iteration history");
+            overrides.push("history: true".to_string());
+        }
+        if args[i] == *"--format" || args[i] == *"-f" {
+            match value_of(&args, i) {
+                "md" => markdown = true,
+                "csv" => markdown = false,
+                _ => {
+                    println!("[nxc].....ERR: table format must be csv or md");
+                    process::exit(1);
+                }
+            }
+        }
+    }
+
+    // `options` blocks are read in order, so a block at the end overrides the file's own
+    if !overrides.is_empty() {
+        system = format!("{system}\noptions {{\n{}\n}}\n", overrides.join("\n"));
     }
 
-    let (soln, log) = match solve(&system, tolerance, max_iterations, allow_nonconvergence) {
+    if !cases.is_empty() {
+        let outputs: Vec<&str> = outputs.iter().map(|i| i.as_str()).collect();
+        let table = match build(&system, &config)
+            .and_then(|mut i| i.sweep(&cases, &outputs)) 
+        {
+            Ok(o) => o,
+            Err(e) => {
+                println!("[nxc].....ERR: nxc could not solve the sweep");
+                println!("[nxc].....{e}");
+                process::exit(1);
+            }
+        };
+
+        let (output, ext) = if markdown {
+            (table.to_markdown(), ".md")
+        } else {
+            (table.to_csv(), ".csv")
+        };
+
+        if output_file {
+            match write(args[1].replace(".nxs", ext), output) {
+                Ok(_) => process::exit(0),
+                Err(_) => {
+                    println!("[nxc].....ERR: nxc could not write to the output file");
+                    process::exit(1);
+                }
+            }
+        } else {
+            println!("{output}");
+            process::exit(0);
+        }
+    }
+
+    let (soln, log) = match solve(&system, &config) {
         Ok(o) => o,
         Err(e) => {
             println!("[nxc].....ERR: nxc could not solve the system");
-            println!("[nxc].....{e}");
+            
+            let mut log = &vec![];
+            if let Some(c) = e.downcast_ref::<SolverConstraintError>() {
+                for line in c.to_string().lines() {
+                    println!("[nxc].....{line}");
+                }
+                println!("[nxc].....partial solution:");
+                for (k, v) in &c.solution {
+                    println!("[nxc].....    {k} = {}", v.as_f64());
+                }
+                log = &c.log;
+            } else if let Some(c) = e.downcast_ref::<SolverConvergenceError>() {
+                println!("[nxc].....{c}");
+                log = &c.log;
+            } else {
+                println!("[nxc].....{e}");
+            }
+            if let Some(path) = history {
+                write_history(&path, log);
+            }
             process::exit(1);
         }
     };
 
+    if let Some(path) = history {
+        write_history(&path, &log);
+    }
+
     let output = format!(
         "[->] Nexsys - {} results:\n\nSolution:\n+=======+\n{}\nProcedure:\n+========+\n{}\n",
         &args[1],
         soln.into_iter().map(|i| format!("{} = {}\n", i.0, i.1.as_f64())).collect::<String>(),
-        log.join("\n")
+        log.iter().map(|i| i.to_string()).collect::<Vec<String>>().join("\n")
     );
 
     if output_file {
@@ -109,3 +252,25 @@ OPTIONS:
         process::exit(0);
     }
 }
+
+/// Returns the value given after the option at `args[i]`, exiting if there isn't one.
+fn value_of(args: &[String], i: usize) -> &str {
+    match args.get(i+1) {
+        Some(o) => o,
+        None => {
+            println!("[nxc].....ERR: {} needs a value", args[i]);
+            process::exit(1);
+        }
+    }
+}
+
+/// Writes the iteration history of the steps in `log` to a .csv file at `path`.
+fn write_history(path: &str, log: &[SolveStep]) {
+    match write(path, history_table(log).to_csv()) {
+        Ok(_) => println!("[nxc].....wrote iteration history to {path}"),
+        Err(_) => {
+            println!("[nxc].....ERR: nxc could not write to the history file");
+            process::exit(1);
+        }
+    }
+}
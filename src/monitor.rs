@@ -0,0 +1,161 @@
+use std::{collections::HashMap, error::Error, fmt, sync::{Arc, atomic::{AtomicBool, Ordering}}, time::{Duration, Instant}};
+use crate::{algos::Method, errors::{SolverCancelledError, SolverTimeoutError}, solver::SolveStep};
+
+/// Receives events from a solve while it runs, e.g. to show its progress.
+/// Every method does nothing by default, so only the events of interest
+/// need to be implemented.
+/// # Example
+/// ```
+/// use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+/// use nexsys::{solve, algos::Method, config::SolverConfig, monitor::Observer};
+///
+/// #[derive(Default)]
+/// struct Counter(AtomicUsize);
+/// impl Observer for Counter {
+///     fn iteration(&self, _method: Method, _iteration: usize, _residual: f64) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let counter = Arc::new(Counter::default());
+/// let config = SolverConfig::builder().observer(counter.clone()).build();
+/// solve("x^2 + y = 11\nx + y^2 = 7", &config).unwrap();
+///
+/// assert!(counter.0.load(Ordering::Relaxed) > 0);
+/// ```
+pub trait Observer: Send + Sync {
+    /// Called after each iteration of an iterative method, with the iteration
+    /// number and the (absolute) residual reached.
+    fn iteration(&self, _method: Method, _iteration: usize, _residual: f64) {}
+
+    /// Called before the solver starts on an equation or block of equations.
+    fn start(&self, _equations: &[String], _targets: &[String]) {}
+
+    /// Called whenever the solver adds a step to its log, including failed
+    /// attempts that are followed by a fallback method.
+    fn step(&self, _step: &SolveStep) {}
+}
+
+/// A flag that can be set from anywhere to stop a solve between iterations.
+/// Clones of a token share the same flag.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+impl CancellationToken {
+    /// Initializes a new token that hasn't been cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Asks every solve using this token to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if `cancel` has been called on this token or any of its clones.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The hooks used to observe and stop a solve while it runs (see `Settings::monitor`).
+#[derive(Clone)]
+#[derive(Default)]
+pub struct Monitor {
+    observer: Option<Arc<dyn Observer>>,
+    cancellation: Option<CancellationToken>,
+    deadline: Option<(Instant, Duration)>
+}
+impl Monitor {
+    /// Initializes a `Monitor` that doesn't observe or stop anything.
+    pub fn new() -> Monitor {
+        Monitor::default()
+    }
+
+    /// Sends the events of the solve to `observer`.
+    pub fn observer(mut self, observer: Arc<dyn Observer>) -> Monitor {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Stops the solve once `token` is cancelled.
+    pub fn cancellation(mut self, token: CancellationToken) -> Monitor {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Stops the solve once `limit` has passed, counting from now.
+    pub fn time_limit(mut self, limit: Duration) -> Monitor {
+        self.deadline = Some((Instant::now() + limit, limit));
+        self
+    }
+
+    /// Returns a `SolverCancelledError` if the solve has been cancelled, or a
+    /// `SolverTimeoutError` if it has run out of time. The errors carry an empty
+    /// solution, which `Nexsys` replaces with the partial solution it found.
+    pub fn check(&self) -> Result<(), Box<dyn Error>> {
+        if self.cancellation.as_ref().is_some_and(|i| i.is_cancelled()) {
+            return Err(Box::new(SolverCancelledError { solution: HashMap::new() }))
+        }
+        match self.deadline {
+            Some((deadline, limit)) if Instant::now() >= deadline => {
+                Err(Box::new(SolverTimeoutError { limit, solution: HashMap::new() }))
+            },
+            _ => Ok(())
+        }
+    }
+
+    /// Reports an iteration to the observer, then checks whether the solve
+    /// should stop (see `Monitor::check`).
+    pub fn iteration(&self, method: Method, iteration: usize, residual: f64) -> Result<(), Box<dyn Error>> {
+        if let Some(o) = &self.observer {
+            o.iteration(method, iteration, residual);
+        }
+        self.check()
+    }
+
+    /// Reports the start of an equation or block of equations to the observer.
+    pub fn start(&self, equations: &[String], targets: &[String]) {
+        if let Some(o) = &self.observer {
+            o.start(equations, targets);
+        }
+    }
+
+    /// Reports a step of the solver's log to the observer.
+    pub fn step(&self, step: &SolveStep) {
+        if let Some(o) = &self.observer {
+            o.step(step);
+        }
+    }
+}
+
+/// Returns `true` if `error` means that the solve was cancelled or ran out of time,
+/// rather than that an algorithm failed. Such errors should not be caught by fallbacks.
+pub fn is_interruption(error: &(dyn Error + 'static)) -> bool {
+    error.is::<SolverCancelledError>() || error.is::<SolverTimeoutError>()
+}
+
+impl fmt::Debug for Monitor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Monitor")
+            .field("observer", &self.observer.is_some())
+            .field("cancellation", &self.cancellation)
+            .field("deadline", &self.deadline)
+            .finish()
+    }
+}
+impl PartialEq for Monitor {
+    /// Monitors are equal if they share the same observer and token, and have the same deadline.
+    fn eq(&self, other: &Monitor) -> bool {
+        let observers = match (&self.observer, &other.observer) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none()
+        };
+        let tokens = match (&self.cancellation, &other.cancellation) {
+            (Some(a), Some(b)) => Arc::ptr_eq(&a.0, &b.0),
+            (a, b) => a.is_none() && b.is_none()
+        };
+        observers && tokens && self.deadline == other.deadline
+    }
+}
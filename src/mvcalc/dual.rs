@@ -0,0 +1,190 @@
+use std::ops::{Add, Sub, Mul, Div, Neg, Rem};
+
+/// A dual number `re + du*ε` where `ε^2 = 0`. Evaluating a function with dual
+/// numbers yields its derivative (in `du`) alongside its value (in `re`),
+/// which is exact up to floating point error.
+/// # Example
+/// ```
+/// use nexsys::mvcalc::Dual;
+///
+/// let x = Dual::var(3.0);
+/// let y = x * x + Dual::from(2.0) * x;
+///
+/// assert_eq!(y.re, 15.0);
+/// assert_eq!(y.du, 8.0);
+/// ```
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct Dual {
+    /// The value of the number.
+    pub re: f64,
+    /// The derivative of the number.
+    pub du: f64
+}
+impl Dual {
+    /// Initializes a new dual number.
+    pub fn new(re: f64, du: f64) -> Dual {
+        Dual { re, du }
+    }
+
+    /// Initializes the dual number for the variable that is being differentiated with respect to.
+    pub fn var(re: f64) -> Dual {
+        Dual { re, du: 1.0 }
+    }
+
+    /// Applies a function to the value of the number, where `df` is the derivative of the function at `self.re`.
+    fn chain(self, f: f64, df: f64) -> Dual {
+        Dual { re: f, du: if self.du == 0.0 { 0.0 } else { df * self.du } }
+    }
+
+    /// Raises the number to the power of `rhs`.
+    pub fn powf(self, rhs: Dual) -> Dual {
+        let re = self.re.powf(rhs.re);
+        let mut du = 0.0;
+        if self.du != 0.0 {
+            du += rhs.re * self.re.powf(rhs.re - 1.0) * self.du;
+        }
+        if rhs.du != 0.0 {
+            du += re * self.re.ln() * rhs.du;
+        }
+        Dual { re, du }
+    }
+
+    /// Returns `sqrt` of the number.
+    pub fn sqrt(self) -> Dual {
+        let f = self.re.sqrt();
+        self.chain(f, 0.5 / f)
+    }
+
+    /// Returns `exp` of the number.
+    pub fn exp(self) -> Dual {
+        let f = self.re.exp();
+        self.chain(f, f)
+    }
+
+    /// Returns `ln` of the number.
+    pub fn ln(self) -> Dual {
+        self.chain(self.re.ln(), 1.0 / self.re)
+    }
+
+    /// Returns `abs` of the number.
+    pub fn abs(self) -> Dual {
+        self.chain(self.re.abs(), self.re.signum())
+    }
+
+    /// Returns `sin` of the number.
+    pub fn sin(self) -> Dual {
+        self.chain(self.re.sin(), self.re.cos())
+    }
+
+    /// Returns `cos` of the number.
+    pub fn cos(self) -> Dual {
+        self.chain(self.re.cos(), -self.re.sin())
+    }
+
+    /// Returns `tan` of the number.
+    pub fn tan(self) -> Dual {
+        let f = self.re.tan();
+        self.chain(f, 1.0 + f * f)
+    }
+
+    /// Returns `asin` of the number.
+    pub fn asin(self) -> Dual {
+        self.chain(self.re.asin(), 1.0 / (1.0 - self.re * self.re).sqrt())
+    }
+
+    /// Returns `acos` of the number.
+    pub fn acos(self) -> Dual {
+        self.chain(self.re.acos(), -1.0 / (1.0 - self.re * self.re).sqrt())
+    }
+
+    /// Returns `atan` of the number.
+    pub fn atan(self) -> Dual {
+        self.chain(self.re.atan(), 1.0 / (1.0 + self.re * self.re))
+    }
+
+    /// Returns `sinh` of the number.
+    pub fn sinh(self) -> Dual {
+        self.chain(self.re.sinh(), self.re.cosh())
+    }
+
+    /// Returns `cosh` of the number.
+    pub fn cosh(self) -> Dual {
+        self.chain(self.re.cosh(), self.re.sinh())
+    }
+
+    /// Returns `tanh` of the number.
+    pub fn tanh(self) -> Dual {
+        let f = self.re.tanh();
+        self.chain(f, 1.0 - f * f)
+    }
+
+    /// Returns `asinh` of the number.
+    pub fn asinh(self) -> Dual {
+        self.chain(self.re.asinh(), 1.0 / (self.re * self.re + 1.0).sqrt())
+    }
+
+    /// Returns `acosh` of the number.
+    pub fn acosh(self) -> Dual {
+        self.chain(self.re.acosh(), 1.0 / (self.re * self.re - 1.0).sqrt())
+    }
+
+    /// Returns `atanh` of the number.
+    pub fn atanh(self) -> Dual {
+        self.chain(self.re.atanh(), 1.0 / (1.0 - self.re * self.re))
+    }
+
+    /// Applies a piecewise constant function (e.g. `floor`) to the number.
+    pub fn step(self, f: impl Fn(f64) -> f64) -> Dual {
+        Dual { re: f(self.re), du: 0.0 }
+    }
+
+    /// Returns the four quadrant arctangent of `self` (y) and `other` (x).
+    pub fn atan2(self, other: Dual) -> Dual {
+        let (y, x) = (self, other);
+        let r = x.re * x.re + y.re * y.re;
+        Dual { re: y.re.atan2(x.re), du: (x.re * y.du - y.re * x.du) / r }
+    }
+}
+impl From<f64> for Dual {
+    fn from(re: f64) -> Dual {
+        Dual { re, du: 0.0 }
+    }
+}
+impl Add for Dual {
+    type Output = Dual;
+    fn add(self, rhs: Dual) -> Dual {
+        Dual { re: self.re + rhs.re, du: self.du + rhs.du }
+    }
+}
+impl Sub for Dual {
+    type Output = Dual;
+    fn sub(self, rhs: Dual) -> Dual {
+        Dual { re: self.re - rhs.re, du: self.du - rhs.du }
+    }
+}
+impl Mul for Dual {
+    type Output = Dual;
+    fn mul(self, rhs: Dual) -> Dual {
+        Dual { re: self.re * rhs.re, du: self.du * rhs.re + self.re * rhs.du }
+    }
+}
+impl Div for Dual {
+    type Output = Dual;
+    fn div(self, rhs: Dual) -> Dual {
+        Dual { re: self.re / rhs.re, du: (self.du * rhs.re - self.re * rhs.du) / (rhs.re * rhs.re) }
+    }
+}
+impl Rem for Dual {
+    type Output = Dual;
+    fn rem(self, rhs: Dual) -> Dual {
+        Dual { re: self.re % rhs.re, du: self.du - (self.re / rhs.re).trunc() * rhs.du }
+    }
+}
+impl Neg for Dual {
+    type Output = Dual;
+    fn neg(self) -> Dual {
+        Dual { re: -self.re, du: -self.du }
+    }
+}
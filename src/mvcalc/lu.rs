@@ -0,0 +1,195 @@
+use std::error::Error;
+use crate::errors::{NxNMultiplicationError, SingularMatrixError};
+
+/// The LU factorization of an `NxN` matrix with partial (row) pivoting, such
+/// that `PA = LU`. Created with `NxN::lu()`. Once a matrix is factored,
+/// systems of equations with that matrix can be solved cheaply.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct LU {
+    /// `L` (below the diagonal, with an implied unit diagonal) and `U`
+    /// (on and above the diagonal) packed into one row-major matrix.
+    lu: Vec<Vec<f64>>,
+    /// `perm[i]` is the row of the original matrix that ended up in row `i`.
+    perm: Vec<usize>,
+    /// `1.0` if an even number of row swaps were made, otherwise `-1.0`.
+    sign: f64,
+    /// The 1-norm of the original matrix.
+    norm: f64
+}
+impl LU {
+    /// Factors a matrix given as rows. Columns without a usable pivot are
+    /// reported in a `SingularMatrixError` using the names given in `vars`.
+    pub(crate) fn factor(mut a: Vec<Vec<f64>>, vars: Option<&Vec<String>>) -> Result<LU, Box<dyn Error>> {
+        let n = a.len();
+        let norm = (0..n)
+            .map(|c| a.iter().map(|r| r[c].abs()).sum::<f64>())
+            .fold(0.0, f64::max);
+
+        let mut perm: Vec<usize> = (0..n).collect();
+        let mut sign = 1.0;
+        let mut dependent = vec![];
+
+        let mut r = 0; // the next row to be pivoted on, which only lags `k` if the matrix is singular
+        for k in 0..n {
+            // find the largest pivot in this column
+            let p = match (r..n).max_by(|&i, &j| a[i][k].abs().total_cmp(&a[j][k].abs())) {
+                Some(p) => p,
+                None => {
+                    dependent.push(k);
+                    continue
+                }
+            };
+
+            let pivot = a[p][k].abs();
+            if pivot <= f64::EPSILON * norm * n as f64 || pivot.is_nan() {
+                dependent.push(k);
+                continue
+            }
+
+            if p != r {
+                a.swap(p, r);
+                perm.swap(p, r);
+                sign = -sign;
+            }
+
+            let (top, bottom) = a.split_at_mut(r + 1);
+            let pivot_row = &top[r];
+            for row in bottom {
+                let m = row[k] / pivot_row[k];
+                row[k] = m;
+                for (x, y) in row[k+1..].iter_mut().zip(&pivot_row[k+1..]) {
+                    *x -= m * y;
+                }
+            }
+            r += 1;
+        }
+
+        if !dependent.is_empty() {
+            let vars = dependent.iter()
+                .map(|&k| match vars {
+                    Some(v) => v[k].to_string(),
+                    None => format!("column {k}")
+                })
+                .collect();
+            return Err(Box::new(SingularMatrixError { vars }))
+        }
+
+        Ok(LU { lu: a, perm, sign, norm })
+    }
+
+    /// Returns the size of the factored matrix.
+    pub fn size(&self) -> usize {
+        self.lu.len()
+    }
+
+    /// Solves `Ax = rhs` for `x`.
+    pub fn solve(&self, rhs: &[f64]) -> Result<Vec<f64>, Box<dyn Error>> {
+        let n = self.size();
+        if rhs.len() != n {
+            return Err(Box::new(NxNMultiplicationError))
+        }
+
+        // forward substitution with L
+        let mut x: Vec<f64> = self.perm.iter().map(|&i| rhs[i]).collect();
+        for i in 0..n {
+            for j in 0..i {
+                x[i] -= self.lu[i][j] * x[j];
+            }
+        }
+
+        // back substitution with U
+        for i in (0..n).rev() {
+            for j in i+1..n {
+                x[i] -= self.lu[i][j] * x[j];
+            }
+            x[i] /= self.lu[i][i];
+        }
+        Ok(x)
+    }
+
+    /// Solves `Aᵀx = rhs` for `x`.
+    pub fn solve_transpose(&self, rhs: &[f64]) -> Result<Vec<f64>, Box<dyn Error>> {
+        let n = self.size();
+        if rhs.len() != n {
+            return Err(Box::new(NxNMultiplicationError))
+        }
+
+        // forward substitution with Uᵀ
+        let mut w = rhs.to_vec();
+        for i in 0..n {
+            for j in 0..i {
+                w[i] -= self.lu[j][i] * w[j];
+            }
+            w[i] /= self.lu[i][i];
+        }
+
+        // back substitution with Lᵀ
+        for i in (0..n).rev() {
+            for j in i+1..n {
+                w[i] -= self.lu[j][i] * w[j];
+            }
+        }
+
+        // undo the row swaps
+        let mut x = vec![0.0; n];
+        for (i, &p) in self.perm.iter().enumerate() {
+            x[p] = w[i];
+        }
+        Ok(x)
+    }
+
+    /// Returns the determinant of the factored matrix.
+    pub fn det(&self) -> f64 {
+        self.sign * (0..self.size()).map(|i| self.lu[i][i]).product::<f64>()
+    }
+
+    /// Returns an estimate of the 1-norm condition number of the factored matrix
+    /// (Hager's method). Large values mean that solutions of `Ax = b` are sensitive
+    /// to small changes in `A` or `b`, e.g. from rounding error.
+    pub fn condition(&self) -> f64 {
+        condition(self.size(), self.norm, |x| self.solve(x), |x| self.solve_transpose(x))
+    }
+}
+
+/// Estimates the 1-norm condition number of a factored matrix from its 1-norm and 
+/// solves with the matrix and its transpose (Hager's method).
+pub(crate) fn condition(
+    n: usize,
+    norm: f64,
+    solve: impl Fn(&[f64]) -> Result<Vec<f64>, Box<dyn Error>>,
+    solve_transpose: impl Fn(&[f64]) -> Result<Vec<f64>, Box<dyn Error>>
+) -> f64 {
+    let mut x = vec![1.0 / n as f64; n];
+    let mut estimate = 0.0;
+
+    for _ in 0..5 {
+        let y = match solve(&x) {
+            Ok(y) => y,
+            Err(_) => return f64::INFINITY
+        };
+        estimate = y.iter().map(|i| i.abs()).sum::<f64>();
+
+        let xi: Vec<f64> = y.iter().map(|i| if *i >= 0.0 { 1.0 } else { -1.0 }).collect();
+        let z = match solve_transpose(&xi) {
+            Ok(z) => z,
+            Err(_) => return f64::INFINITY
+        };
+
+        let (j, z_max) = z.iter()
+            .map(|i| i.abs())
+            .enumerate()
+            .fold((0, 0.0), |a, b| if b.1 > a.1 { b } else { a });
+
+        let zx: f64 = z.iter().zip(&x).map(|(a, b)| a * b).sum();
+        if z_max <= zx {
+            break
+        }
+
+        x = vec![0.0; n];
+        x[j] = 1.0;
+    }
+
+    norm * estimate
+}
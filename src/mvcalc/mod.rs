@@ -1,4 +1,8 @@
 mod nxn;
+mod dual;
+mod lu;
+mod sparse;
+mod svd;
 
 use meval::eval_str_with_context;
 use std::{
@@ -11,10 +15,19 @@ use std::{
 };
 use crate::{
     algos::{Variable, new_context}, 
+    expr::Expr,
     errors::{NxNMultiplicationError, RoundingError, VecMultiplicationError}
 };
 
 pub use nxn::NxN;
+pub use dual::Dual;
+pub use lu::LU;
+pub use sparse::{Sparse, SparseLU};
+pub use svd::SVD;
+
+/// Matrices with a larger condition number than this are treated as singular, both when 
+/// an `LU` factorization is checked and when an `SVD` decides which singular values to ignore.
+pub const MAX_CONDITION: f64 = 1E12;
 
 /// Rounds a value to the specified number of decimal places
 pub fn round(num: f64, places: usize) -> Result<f64, Box<dyn Error>>{
@@ -103,6 +116,7 @@ where
 }
 
 /// Returns the dot product of two given vectors.
+#[allow(clippy::ptr_arg)] // taking slices would break callers that name the types
 pub fn vec_vec_dot<T, U>(lhs: &Vec<T>, rhs: &Vec<U>) -> Result<T, Box<dyn Error>> 
 where   
     T: Copy + Mul<U> + Sum::<<T as Mul<U>>::Output>,
@@ -161,7 +175,7 @@ where
 /// Returns a tuple of `Vec`s that contain the keys and values of the original HashMap. 
 /// The index of the key will be the same as its corresponding value's index.
 /// 
-/// This function only exists for use in `pub fn jacobian()`.
+/// This function only exists for use in `fn build_jacobian()`.
 fn split_hm<K, V>(hm: HashMap<K, V>) -> (Vec<K>, Vec<V>) {
     let mut keys = Vec::new();
     let mut vals = Vec::new();
@@ -174,7 +188,8 @@ fn split_hm<K, V>(hm: HashMap<K, V>) -> (Vec<K>, Vec<V>) {
     (keys, vals)
 }
 
-/// Returns the (numerical) `NxN` Jacobian matrix of a given system of equations at the vector given by `guess`.
+/// Returns the `NxN` Jacobian matrix of a given system of equations at the vector given by `guess`.
+/// The partial derivatives are found exactly by evaluating the system with dual numbers.
 /// 
 /// Note that the resulting matrix's columns will be in a random order, so extra care is needed to identify which
 /// variable occupies which column by checking the ordering of `self.vars`.
@@ -182,11 +197,12 @@ fn split_hm<K, V>(hm: HashMap<K, V>) -> (Vec<K>, Vec<V>) {
 /// ```
 /// use nexsys::mvcalc::jacobian;
 /// use nexsys::algos::Variable;
+/// use nexsys::expr::Expr;
 /// use std::collections::HashMap;
 /// 
 /// let my_sys = vec![
-///     "x^2 + y",
-///     "y   - x"
+///     Expr::parse("x^2 + y").unwrap(),
+///     Expr::parse("y   - x").unwrap()
 /// ];
 /// let guess = HashMap::from([
 ///     ("x", Variable::new(1.0, None)),
@@ -195,13 +211,77 @@ fn split_hm<K, V>(hm: HashMap<K, V>) -> (Vec<K>, Vec<V>) {
 /// 
 /// let j = jacobian(&my_sys, &guess);
 /// 
-/// // j.to_vec() will return roughly:
+/// // j.to_vec() will return:
 /// // vec![
 /// //      vec![2.0, -1.0],
 /// //      vec![1.0, 1.0]
 /// // ];
 /// ```
-pub fn jacobian(system: &Vec<&str>, guess: &HashMap<&str, Variable>) -> Result<NxN, Box<dyn Error>> {
+pub fn jacobian(system: &[Expr], guess: &HashMap<&str, Variable>) -> Result<NxN, Box<dyn Error>> {
+    build_jacobian(system, guess, |i, target| i.eval_dual(
+        &|v| if v == target { 
+            Some(Dual::var(guess[target].as_f64())) 
+        } else { 
+            guess.get(v).map(|j| Dual::from(j.as_f64())) 
+        }
+    ).map(|d| d.du))
+}
+
+/// Does the same thing as `jacobian()`, but approximates the partial derivatives
+/// with finite differences. This can be used for expressions that are not 
+/// differentiable everywhere.
+pub fn numeric_jacobian(system: &[Expr], guess: &HashMap<&str, Variable>) -> Result<NxN, Box<dyn Error>> {
+    build_jacobian(system, guess, |i, target| {
+        // create a partial function of the target variable
+        let partial = |x: f64| i.eval(
+            &|v| if v == target { Some(x) } else { guess.get(v).map(|j| j.as_f64()) }
+        );
+        d_dx(partial, guess[target].as_f64())
+    })
+}
+
+/// Returns the partial derivatives of each equation in a system w.r.t. each of `vars` 
+/// at the vector given by `guess`, as rows. Unlike `jacobian()`, the system doesn't 
+/// need to be square, and the columns are in the order given by `vars`.
+/// # Example
+/// ```
+/// use nexsys::mvcalc::jacobian_rows;
+/// use nexsys::algos::Variable;
+/// use nexsys::expr::Expr;
+/// use std::collections::HashMap;
+/// 
+/// let my_sys = vec![Expr::parse("x^2 + y*z").unwrap()];
+/// let guess = HashMap::from([
+///     ("x", Variable::new(1.0, None)),
+///     ("y", Variable::new(2.0, None)),
+///     ("z", Variable::new(3.0, None))
+/// ]);
+/// 
+/// let j = jacobian_rows(&my_sys, &guess, &["x", "y", "z"]).unwrap();
+/// 
+/// assert_eq!(j, vec![vec![2.0, 3.0, 2.0]]);
+/// ```
+pub fn jacobian_rows(system: &[Expr], guess: &HashMap<&str, Variable>, vars: &[&str]) -> Result<Vec<Vec<f64>>, Box<dyn Error>> {
+    system.iter()
+        .map(|i| vars.iter()
+            .map(|&target| i.eval_dual(
+                &|v| if v == target { 
+                    guess.get(v).map(|j| Dual::var(j.as_f64())) 
+                } else { 
+                    guess.get(v).map(|j| Dual::from(j.as_f64())) 
+                }
+            ).map(|d| d.du))
+            .collect()
+        )
+        .collect()
+}
+
+/// Assembles a Jacobian matrix column by column, using `partial` to find the 
+/// derivative of an equation w.r.t. a variable.
+fn build_jacobian<F>(system: &[Expr], guess: &HashMap<&str, Variable>, partial: F) -> Result<NxN, Box<dyn Error>> 
+where
+    F: Fn(&Expr, &str) -> Result<f64, Box<dyn Error>>
+{
     if system.len() != guess.keys().len() { 
         panic!("ERR: System is not properly constrained!") // guard clause against invalid problems
     } 
@@ -210,27 +290,89 @@ pub fn jacobian(system: &Vec<&str>, guess: &HashMap<&str, Variable>) -> Result<N
     let mut mat = Vec::new();
     let vec = split_hm(guess.clone());
 
-    let mut err = None; // storage for any meval errors returned during matrix creation
-
     for c in 0..size {
-        let col = Vec::from_iter(
-            system.iter().map(
-                |&i| match partial_d_dx(i, guess, vec.0[c]) {
-                    Ok(o) => o,
-                    Err(e) => {
-                        err = Some(e);  // now we can report errors from this closure
-                        f64::NAN        // ...and still meet the `-> f64` requirement!
-                    }
-                }
-            )
-        );
+        let target = vec.0[c];
+        let mut col = Vec::with_capacity(size);
 
-        if let Some(e) = err {
-            return Err(e);
+        for i in system {
+            col.push(partial(i, target)?);
         }
 
         mat.push(col);
     };
 
     NxN::from_cols( mat, Some(vec.0) )
+}
+
+
+/// Returns the `Sparse` Jacobian matrix of a given system of equations at the vector given by `guess`.
+/// Only the partial derivatives of each equation w.r.t. the variables that it uses are evaluated.
+/// 
+/// Like `jacobian()`, the columns are in a random order, so `self.vars` should be checked to 
+/// identify which variable occupies which column.
+/// # Example
+/// ```
+/// use nexsys::mvcalc::sparse_jacobian;
+/// use nexsys::algos::Variable;
+/// use nexsys::expr::Expr;
+/// use std::collections::HashMap;
+/// 
+/// let my_sys = vec![
+///     Expr::parse("x^2 + y").unwrap(),
+///     Expr::parse("y - 3").unwrap()
+/// ];
+/// let guess = HashMap::from([
+///     ("x", Variable::new(1.0, None)),
+///     ("y", Variable::new(1.0, None))
+/// ]);
+/// 
+/// let j = sparse_jacobian(&my_sys, &guess).unwrap();
+/// 
+/// assert_eq!(j.nonzeros(), 3);
+/// ```
+pub fn sparse_jacobian(system: &[Expr], guess: &HashMap<&str, Variable>) -> Result<Sparse, Box<dyn Error>> {
+    build_sparse_jacobian(system, guess, |i, target| i.eval_dual(
+        &|v| if v == target { 
+            Some(Dual::var(guess[target].as_f64())) 
+        } else { 
+            guess.get(v).map(|j| Dual::from(j.as_f64())) 
+        }
+    ).map(|d| d.du))
+}
+
+/// Does the same thing as `sparse_jacobian()`, but approximates the partial derivatives
+/// with finite differences.
+pub fn numeric_sparse_jacobian(system: &[Expr], guess: &HashMap<&str, Variable>) -> Result<Sparse, Box<dyn Error>> {
+    build_sparse_jacobian(system, guess, |i, target| {
+        let partial = |x: f64| i.eval(
+            &|v| if v == target { Some(x) } else { guess.get(v).map(|j| j.as_f64()) }
+        );
+        d_dx(partial, guess[target].as_f64())
+    })
+}
+
+/// Assembles a sparse Jacobian matrix row by row, using `partial` to find the 
+/// derivative of an equation w.r.t. each variable that the equation uses.
+fn build_sparse_jacobian<F>(system: &[Expr], guess: &HashMap<&str, Variable>, partial: F) -> Result<Sparse, Box<dyn Error>> 
+where
+    F: Fn(&Expr, &str) -> Result<f64, Box<dyn Error>>
+{
+    if system.len() != guess.keys().len() { 
+        panic!("ERR: System is not properly constrained!") // guard clause against invalid problems
+    } 
+
+    let vars: Vec<String> = guess.keys().map(|i| i.to_string()).collect();
+    let cols: HashMap<&str, usize> = vars.iter().enumerate().map(|(c, v)| (v.as_str(), c)).collect();
+    let mut mat = Sparse::new(system.len(), None);
+
+    for (r, i) in system.iter().enumerate() {
+        for v in i.vars() {
+            if let Some(&c) = cols.get(v.as_str()) {
+                mat.set(r, c, partial(i, &v)?);
+            }
+        }
+    }
+
+    mat.vars = Some(vars);
+    Ok(mat)
 }
\ No newline at end of file
@@ -380,4 +380,92 @@ impl NxN {
     pub fn to_vec(self) -> Vec<Vec<f64>> {
         self.mat
     }
+
+    /// Multiplies each row of the matrix by the matching value in `rows` and each 
+    /// column by the matching value in `cols`, i.e. replaces `A` with `RAC`.
+    /// # Example
+    /// ```
+    /// use nexsys::mvcalc::NxN;
+    /// 
+    /// let mut my_matrix = NxN::from_cols(vec![
+    ///     vec![1.0, 1.0],
+    ///     vec![1.0, 1.0]
+    /// ], None).unwrap();
+    /// my_matrix.scale(&[1.0, 2.0], &[10.0, 100.0]);
+    /// 
+    /// assert_eq!(my_matrix.to_vec(), vec![vec![10.0, 20.0], vec![100.0, 200.0]]);
+    /// ```
+    pub fn scale(&mut self, rows: &[f64], cols: &[f64]) {
+        for (col, c) in self.mat.iter_mut().zip(cols) {
+            for (val, r) in col.iter_mut().zip(rows) {
+                *val *= r * c;
+            }
+        }
+    }
+
+    /// Returns the LU factorization of the matrix, found with partial pivoting. 
+    /// If the matrix is singular, a `SingularMatrixError` naming the variables 
+    /// whose columns are linearly dependent on the others is returned.
+    /// # Example
+    /// ```
+    /// use nexsys::mvcalc::NxN;
+    /// 
+    /// let my_matrix = NxN::from_cols(vec![
+    ///     vec![1.0, 2.0, 3.0],
+    ///     vec![2.0, 4.0, 6.0],
+    ///     vec![0.0, 1.0, 1.0]
+    /// ], Some(vec!["x", "y", "z"])).unwrap();
+    /// 
+    /// let err = my_matrix.lu().unwrap_err();
+    /// 
+    /// assert!(err.to_string().contains("`y`"));
+    /// ```
+    pub fn lu(&self) -> Result<LU, Box<dyn Error>> {
+        let rows = (0..self.size).map(|i| self.get_row(i)).collect();
+        LU::factor(rows, self.vars.as_ref())
+    }
+
+    /// Solves `Ax = rhs` for `x` via LU factorization, where `A` is the matrix.
+    /// This is faster and more accurate than inverting the matrix.
+    /// # Example
+    /// ```
+    /// use nexsys::mvcalc::NxN;
+    /// 
+    /// let my_matrix = NxN::from_cols(vec![
+    ///     vec![0.0, 1.0],
+    ///     vec![2.0, 3.0]
+    /// ], None).unwrap();
+    /// 
+    /// assert_eq!(my_matrix.solve(&[4.0, 7.0]).unwrap(), vec![1.0, 2.0]);
+    /// ```
+    pub fn solve(&self, rhs: &[f64]) -> Result<Vec<f64>, Box<dyn Error>> {
+        self.lu()?.solve(rhs)
+    }
+
+    /// Returns the determinant of the matrix, which is `0.0` if the matrix is singular.
+    pub fn det(&self) -> f64 {
+        self.lu().map_or(0.0, |lu| lu.det())
+    }
+
+    /// Returns an estimate of the 1-norm condition number of the matrix, 
+    /// which is infinite if the matrix is singular.
+    pub fn condition(&self) -> f64 {
+        self.lu().map_or(f64::INFINITY, |lu| lu.condition())
+    }
+
+    /// Returns the singular value decomposition of the matrix.
+    /// # Example
+    /// ```
+    /// use nexsys::mvcalc::NxN;
+    /// 
+    /// let my_matrix = NxN::from_cols(vec![
+    ///     vec![3.0, 0.0],
+    ///     vec![0.0, -4.0]
+    /// ], None).unwrap();
+    /// 
+    /// assert_eq!(my_matrix.svd().singular_values(), &[4.0, 3.0]);
+    /// ```
+    pub fn svd(&self) -> SVD {
+        SVD::decompose(self.mat.clone(), self.vars.clone())
+    }
 }
\ No newline at end of file
@@ -0,0 +1,271 @@
+use std::{collections::BTreeMap, error::Error};
+use crate::{
+    mvcalc::{NxN, lu::condition},
+    errors::{NxNMultiplicationError, SingularMatrixError}
+};
+
+/// An n x n matrix that only stores its nonzero values. This is much cheaper than
+/// `NxN` for large systems where each equation only uses a few of the variables.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct Sparse {
+    pub size: usize,
+    pub vars: Option<Vec<String>>,  // Optional header column for annotating which variables are in each column
+    rows: Vec<BTreeMap<usize, f64>>
+}
+impl Sparse {
+    /// Initializes an empty (all zero) sparse matrix of the specified size.
+    /// # Example
+    /// ```
+    /// use nexsys::mvcalc::Sparse;
+    ///
+    /// let mut my_matrix = Sparse::new(1000, None);
+    /// my_matrix.set(2, 998, 5.0);
+    ///
+    /// assert_eq!(my_matrix.get(2, 998), 5.0);
+    /// assert_eq!(my_matrix.get(998, 2), 0.0);
+    /// assert_eq!(my_matrix.nonzeros(), 1);
+    /// ```
+    pub fn new(size: usize, vars: Option<Vec<String>>) -> Sparse {
+        Sparse { size, vars, rows: vec![BTreeMap::new(); size] }
+    }
+
+    /// Sets the value at the given row and column. Setting a value to zero removes it.
+    pub fn set(&mut self, row: usize, col: usize, value: f64) {
+        if value == 0.0 {
+            self.rows[row].remove(&col);
+        } else {
+            self.rows[row].insert(col, value);
+        }
+    }
+
+    /// Returns the value at the given row and column.
+    pub fn get(&self, row: usize, col: usize) -> f64 {
+        self.rows[row].get(&col).copied().unwrap_or(0.0)
+    }
+
+    /// Returns the number of nonzero values in the matrix.
+    pub fn nonzeros(&self) -> usize {
+        self.rows.iter().map(|i| i.len()).sum()
+    }
+
+    /// Multiplies each row of the matrix by the matching value in `rows` and each 
+    /// column by the matching value in `cols`, i.e. replaces `A` with `RAC`.
+    pub fn scale(&mut self, rows: &[f64], cols: &[f64]) {
+        for (row, r) in self.rows.iter_mut().zip(rows) {
+            for (c, val) in row.iter_mut() {
+                *val *= r * cols[*c];
+            }
+        }
+    }
+
+    /// Multiplies the matrix and a column vector.
+    pub fn mul_vec(&self, rhs: &[f64]) -> Result<Vec<f64>, Box<dyn Error>> {
+        if rhs.len() != self.size {
+            return Err(Box::new(NxNMultiplicationError))
+        }
+        Ok(self.rows.iter().map(|r| r.iter().map(|(&c, v)| v * rhs[c]).sum()).collect())
+    }
+
+    /// Returns the matrix as a dense `NxN` matrix.
+    pub fn to_dense(&self) -> Result<NxN, Box<dyn Error>> {
+        let cols = (0..self.size)
+            .map(|c| (0..self.size).map(|r| self.get(r, c)).collect())
+            .collect();
+        NxN::from_cols(cols, self.vars.as_ref().map(|v| v.iter().map(|i| i.as_str()).collect()))
+    }
+
+    /// Returns the LU factorization of the matrix. Rows are pivoted to keep the
+    /// factorization stable, preferring sparse rows among pivots that are nearly
+    /// as large as the largest so that as few new nonzeros are created as possible.
+    /// If the matrix is singular, a `SingularMatrixError` naming the variables
+    /// whose columns are linearly dependent on the others is returned.
+    pub fn lu(&self) -> Result<SparseLU, Box<dyn Error>> {
+        let n = self.size;
+        let mut rows = self.rows.clone();
+        let norm = rows.iter().flat_map(|r| r.values()).fold(0.0_f64, |a, b| a.max(b.abs()));
+
+        // the 1-norm of the matrix, i.e. its largest absolute column sum
+        let mut col_sums = vec![0.0; n];
+        for row in &rows {
+            for (&c, v) in row {
+                col_sums[c] += v.abs();
+            }
+        }
+        let norm_1 = col_sums.into_iter().fold(0.0, f64::max);
+
+        // the rows that still have a value in each column
+        let mut col_rows: Vec<Vec<usize>> = vec![vec![]; n];
+        for (r, row) in rows.iter().enumerate() {
+            for &c in row.keys() {
+                col_rows[c].push(r);
+            }
+        }
+
+        let mut active = vec![true; n];
+        let mut pivots = vec![];
+        let mut ops = vec![];
+        let mut upper = vec![];
+        let mut dependent = vec![];
+
+        for k in 0..n {
+            let candidates: Vec<usize> = col_rows[k].iter()
+                .copied()
+                .filter(|&r| active[r] && rows[r].contains_key(&k))
+                .collect();
+
+            let largest = candidates.iter().map(|&r| rows[r][&k].abs()).fold(0.0, f64::max);
+            if largest <= f64::EPSILON * norm * n as f64 || largest.is_nan() {
+                dependent.push(k);
+                pivots.push(None);
+                upper.push(BTreeMap::new());
+                continue
+            }
+
+            // threshold pivoting: the sparsest row whose pivot is at least a tenth of the largest
+            let p = candidates.iter()
+                .copied()
+                .filter(|&r| rows[r][&k].abs() >= 0.1 * largest)
+                .min_by_key(|&r| rows[r].len())
+                .unwrap(); // the largest pivot always passes the filter
+
+            active[p] = false;
+            let pivot_row = std::mem::take(&mut rows[p]);
+            let a_pk = pivot_row[&k];
+
+            for &i in &candidates {
+                if i == p {
+                    continue
+                }
+                let m = rows[i].remove(&k).unwrap() / a_pk; // candidates have a value in column `k`
+                for (&c, v) in pivot_row.range(k+1..) {
+                    let entry = rows[i].entry(c).or_insert_with(|| {
+                        col_rows[c].push(i); // fill-in
+                        0.0
+                    });
+                    *entry -= m * v;
+                }
+                ops.push((i, p, m));
+            }
+
+            pivots.push(Some(p));
+            upper.push(pivot_row);
+        }
+
+        if !dependent.is_empty() {
+            let vars = dependent.iter()
+                .map(|&k| match &self.vars {
+                    Some(v) => v[k].to_string(),
+                    None => format!("column {k}")
+                })
+                .collect();
+            return Err(Box::new(SingularMatrixError { vars }))
+        }
+
+        Ok(SparseLU {
+            pivots: pivots.into_iter().map(|i| i.unwrap()).collect(), // no pivots are missing if none are dependent
+            ops,
+            upper,
+            norm: norm_1
+        })
+    }
+
+    /// Solves `Ax = rhs` for `x` via sparse LU factorization, where `A` is the matrix.
+    /// # Example
+    /// ```
+    /// use nexsys::mvcalc::Sparse;
+    ///
+    /// // a tridiagonal matrix
+    /// let mut my_matrix = Sparse::new(100, None);
+    /// for i in 0..100 {
+    ///     my_matrix.set(i, i, 2.0);
+    ///     if i > 0 { my_matrix.set(i, i - 1, -1.0); }
+    ///     if i < 99 { my_matrix.set(i, i + 1, -1.0); }
+    /// }
+    ///
+    /// let x = my_matrix.solve(&my_matrix.mul_vec(&vec![1.0; 100]).unwrap()).unwrap();
+    ///
+    /// assert!(x.iter().all(|i| (i - 1.0).abs() < 1e-9));
+    /// ```
+    pub fn solve(&self, rhs: &[f64]) -> Result<Vec<f64>, Box<dyn Error>> {
+        self.lu()?.solve(rhs)
+    }
+}
+
+/// The LU factorization of a `Sparse` matrix. Created with `Sparse::lu()`.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct SparseLU {
+    /// `pivots[k]` is the row that was used to eliminate column `k`.
+    pivots: Vec<usize>,
+    /// The row operations `row[i] -= m * row[p]` that were made, in order, as `(i, p, m)`.
+    ops: Vec<(usize, usize, f64)>,
+    /// The rows of `U`, in column order.
+    upper: Vec<BTreeMap<usize, f64>>,
+    /// The 1-norm of the original matrix.
+    norm: f64
+}
+impl SparseLU {
+    /// Solves `Ax = rhs` for `x`.
+    pub fn solve(&self, rhs: &[f64]) -> Result<Vec<f64>, Box<dyn Error>> {
+        let n = self.pivots.len();
+        if rhs.len() != n {
+            return Err(Box::new(NxNMultiplicationError))
+        }
+
+        // forward substitution by repeating the row operations
+        let mut b = rhs.to_vec();
+        for &(i, p, m) in &self.ops {
+            b[i] -= m * b[p];
+        }
+
+        // back substitution with U
+        let mut x = vec![0.0; n];
+        for k in (0..n).rev() {
+            let row = &self.upper[k];
+            let mut sum = b[self.pivots[k]];
+            for (&c, v) in row.range(k+1..) {
+                sum -= v * x[c];
+            }
+            x[k] = sum / row[&k];
+        }
+        Ok(x)
+    }
+
+    /// Solves `Aᵀx = rhs` for `x`.
+    pub fn solve_transpose(&self, rhs: &[f64]) -> Result<Vec<f64>, Box<dyn Error>> {
+        let n = self.pivots.len();
+        if rhs.len() != n {
+            return Err(Box::new(NxNMultiplicationError))
+        }
+
+        // forward substitution with Uᵀ, one column of U at a time
+        let mut c = rhs.to_vec();
+        let mut w = vec![0.0; n];
+        for k in 0..n {
+            let row = &self.upper[k];
+            w[k] = c[k] / row[&k];
+            for (&j, v) in row.range(k+1..) {
+                c[j] -= v * w[k];
+            }
+        }
+
+        // put each value back in its pivot row, then undo the row operations in reverse
+        let mut x = vec![0.0; n];
+        for (k, &p) in self.pivots.iter().enumerate() {
+            x[p] = w[k];
+        }
+        for &(i, p, m) in self.ops.iter().rev() {
+            x[p] -= m * x[i];
+        }
+        Ok(x)
+    }
+
+    /// Returns an estimate of the 1-norm condition number of the factored matrix 
+    /// (see `LU::condition`).
+    pub fn condition(&self) -> f64 {
+        condition(self.pivots.len(), self.norm, |x| self.solve(x), |x| self.solve_transpose(x))
+    }
+}
@@ -0,0 +1,150 @@
+use std::error::Error;
+use crate::errors::NxNMultiplicationError;
+use super::MAX_CONDITION;
+
+/// Components of a null space direction smaller than this are ignored when naming undetermined variables.
+const NULL_COMPONENT: f64 = 1E-3;
+
+/// The largest number of sweeps made by the Jacobi method.
+const MAX_SWEEPS: usize = 60;
+
+/// The singular value decomposition `A = UΣVᵀ` of an `NxN` matrix. Created with `NxN::svd()`.
+/// Unlike `LU`, this can be used to solve systems with singular or nearly singular matrices
+/// in the least-squares sense, and it shows which directions the matrix can't resolve.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct SVD {
+    /// The left singular vectors, as columns.
+    u: Vec<Vec<f64>>,
+    /// The singular values, in descending order.
+    s: Vec<f64>,
+    /// The right singular vectors, as columns.
+    v: Vec<Vec<f64>>,
+    /// The variables in each column of the original matrix, if they were given.
+    vars: Option<Vec<String>>
+}
+impl SVD {
+    /// Decomposes a matrix given as columns via the one-sided Jacobi method, which 
+    /// orthogonalizes the columns with plane rotations.
+    pub(crate) fn decompose(mut u: Vec<Vec<f64>>, vars: Option<Vec<String>>) -> SVD {
+        let n = u.len();
+        let mut v: Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+
+        for _ in 0..MAX_SWEEPS {
+            let mut rotated = false;
+            for p in 0..n {
+                for q in p+1..n {
+                    let alpha: f64 = u[p].iter().map(|i| i * i).sum();
+                    let beta: f64 = u[q].iter().map(|i| i * i).sum();
+                    let gamma: f64 = u[p].iter().zip(&u[q]).map(|(i, j)| i * j).sum();
+
+                    if gamma.abs() <= f64::EPSILON * (alpha * beta).sqrt() || gamma == 0.0 {
+                        continue
+                    }
+                    rotated = true;
+
+                    let zeta = (beta - alpha) / (2.0 * gamma);
+                    let t = zeta.signum() / (zeta.abs() + (1.0 + zeta * zeta).sqrt());
+                    let c = 1.0 / (1.0 + t * t).sqrt();
+                    let s = c * t;
+
+                    for m in [&mut u, &mut v] {
+                        let (left, right) = m.split_at_mut(q);
+                        for (a, b) in left[p].iter_mut().zip(right[0].iter_mut()) {
+                            (*a, *b) = (c * *a - s * *b, s * *a + c * *b);
+                        }
+                    }
+                }
+            }
+            if !rotated {
+                break
+            }
+        }
+
+        // the singular values are the lengths of the orthogonalized columns
+        let mut order: Vec<(f64, usize)> = u.iter()
+            .map(|i| i.iter().map(|j| j * j).sum::<f64>().sqrt())
+            .zip(0..n)
+            .collect();
+        order.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let s = order.iter().map(|i| i.0).collect();
+        let v = order.iter().map(|&(_, j)| v[j].clone()).collect();
+        let u = order.iter()
+            .map(|&(sigma, j)| u[j].iter().map(|i| if sigma > 0.0 { i / sigma } else { 0.0 }).collect())
+            .collect();
+
+        SVD { u, s, v, vars }
+    }
+
+    /// Returns the singular values in descending order.
+    pub fn singular_values(&self) -> &[f64] {
+        &self.s
+    }
+
+    /// Returns the numerical rank of the matrix, i.e. the number of singular values 
+    /// that aren't negligible compared to the largest. A singular value is negligible if it 
+    /// is within `N / MAX_CONDITION` of the largest: the condition number of an `LU` is a 
+    /// 1-norm estimate, which can be up to `N` times the ratio of the singular values, so 
+    /// any matrix whose `LU` is too poorly conditioned to use has a smaller rank than `N` here.
+    pub fn rank(&self) -> usize {
+        let cutoff = self.s.len() as f64 * self.s.first().copied().unwrap_or(0.0) / MAX_CONDITION;
+        self.s.iter().filter(|&&i| i > cutoff).count()
+    }
+
+    /// Returns the directions (as unit vectors) that the matrix maps to zero, 
+    /// i.e. the right singular vectors of the negligible singular values.
+    pub fn null_space(&self) -> Vec<Vec<f64>> {
+        self.v[self.rank()..].to_vec()
+    }
+
+    /// Returns the variables that take part in the null space of the matrix. These are 
+    /// the variables that a system of equations with this Jacobian can't determine.
+    /// Columns without a variable name are named by their index.
+    pub fn undetermined(&self) -> Vec<String> {
+        let null = self.null_space();
+        (0..self.s.len())
+            .filter(|&c| null.iter().any(|d| d[c].abs() > NULL_COMPONENT))
+            .map(|c| match &self.vars {
+                Some(v) => v[c].to_string(),
+                None => format!("column {c}")
+            })
+            .collect()
+    }
+
+    /// Returns the minimum-norm least-squares solution of `Ax = rhs` (i.e. multiplies 
+    /// `rhs` by the pseudo-inverse of `A`), ignoring the negligible singular values. 
+    /// # Example
+    /// ```
+    /// use nexsys::mvcalc::NxN;
+    /// 
+    /// // both columns are the same, so only x + y is determined
+    /// let my_matrix = NxN::from_cols(vec![
+    ///     vec![1.0, 1.0],
+    ///     vec![1.0, 1.0]
+    /// ], Some(vec!["x", "y"])).unwrap();
+    /// 
+    /// let svd = my_matrix.svd();
+    /// let x = svd.solve(&[2.0, 2.0]).unwrap();
+    /// 
+    /// assert_eq!(svd.rank(), 1);
+    /// assert!((x[0] - 1.0).abs() < 1e-12 && (x[1] - 1.0).abs() < 1e-12);
+    /// assert_eq!(svd.undetermined(), vec!["x", "y"]);
+    /// ```
+    pub fn solve(&self, rhs: &[f64]) -> Result<Vec<f64>, Box<dyn Error>> {
+        let n = self.s.len();
+        if rhs.len() != n {
+            return Err(Box::new(NxNMultiplicationError))
+        }
+
+        let mut x = vec![0.0; n];
+        for k in 0..self.rank() {
+            let coef = self.u[k].iter().zip(rhs).map(|(i, j)| i * j).sum::<f64>() / self.s[k];
+            for (xi, vi) in x.iter_mut().zip(&self.v[k]) {
+                *xi += coef * vi;
+            }
+        }
+        Ok(x)
+    }
+}
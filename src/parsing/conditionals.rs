@@ -95,22 +95,24 @@ pub (in crate) fn format_conditional(cndl: &str) -> Result<String, Box<dyn Error
     Ok(args + " = 0")
 }
 
-/// Identifies and returns conditional statements found in a Nexsys-legal string.
-pub fn conditionals(text: &str) -> Result<String, Box<dyn Error>> {
-    lazy_static!{
-        static ref RE: Regex = Regex::new(            
+lazy_static!{
+    /// Matches a conditional statement, which spans five lines.
+    pub(crate) static ref CONDITIONAL: Regex = Regex::new(            
 r#"(?m)^[ \t]*if [^<>=]+[<>=]{1,2}[^<>=]+:$
 ^.*$
 ^[ \t]*else:$
 ^.*$
 ^[ \t]*end"#
-        ).unwrap();
-    }
+    ).unwrap();
+}
+
+/// Identifies and returns conditional statements found in a Nexsys-legal string.
+pub fn conditionals(text: &str) -> Result<String, Box<dyn Error>> {
     let mut output = text.to_string();
     
     loop {
         let tmp = output.to_string(); //FIXME: this looks stupid. Is there a better way to do it?
-        let cdls: Vec<&str> = RE.find_iter(&tmp).map(|i| i.as_str()).collect();
+        let cdls: Vec<&str> = CONDITIONAL.find_iter(&tmp).map(|i| i.as_str()).collect();
 
         // println!("{cdls:#?}");
     
@@ -17,7 +17,7 @@ r#"(?ms)^[ \t]duplicate (?i)[a-z][a-z0-9_]*(?-i) +[0-9]+, *[0-9]+ *:$
             .split(':')
             .collect::<Vec<&str>>();
 
-        let var = groups[0]
+        let _var = groups[0]
             .trim_start()
             .strip_prefix("duplicate ")
             .unwrap() // This is acceptable because `duplicate ` must be present to match regex
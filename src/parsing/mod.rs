@@ -5,7 +5,8 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use std::{collections::HashMap, error::Error};
 use std::fs::read_to_string;
-use crate::{solve, Variable, units::{convert, const_data}, errors::ConstFormatError};
+use serde_json::{Map, Value};
+use crate::{solve, Variable, algos::{Convergence, Norm, RootChoice}, config::SolverConfig, units::{convert, const_data}, sweep::{Sweep, MAX_CASES}, errors::{ConstFormatError, ConvergenceFormatError, OptionsError, SweepRangeError}};
 
 pub use conditionals::*;
 pub use duplicate::*;
@@ -108,8 +109,253 @@ pub fn domains(text: &str) -> HashMap<String, [f64; 2]> {
     res
 }
 
+/// Identifies and returns parametric sweeps found in a Nexsys-legal string. 
+/// Sweeps are given either as a range (`sweep x from 0 to 1 step 0.1`) 
+/// or as a list of values (`sweep x in [1, 2, 5]`). Ranges must have a positive 
+/// step, and all of the sweeps together may make at most `MAX_CASES` cases.
+pub fn sweeps(text: &str) -> Result<Vec<Sweep>, Box<dyn Error>> {
+    lazy_static!{
+        static ref RANGE: Regex = Regex::new(r"(?im)^[ \t]*sweep ([a-z][a-z0-9_]*) from (\S+) to (\S+) step (\S+)[ \t]*$").unwrap();
+        static ref LIST: Regex = Regex::new(r"(?im)^[ \t]*sweep ([a-z][a-z0-9_]*) in \[(.*)\][ \t]*$").unwrap();
+    }
+    let mut res = vec![];
+
+    for caps in RANGE.captures_iter(text) {
+        let start: f64 = caps[2].parse()?;
+        let end: f64 = caps[3].parse()?;
+        let step: f64 = caps[4].parse()?;
+
+        if !(step > 0.0 && step.is_finite()) {
+            return Err(Box::new(SweepRangeError(format!("the step of `{}` must be positive, found {step}", &caps[1]))))
+        }
+        if !(start.is_finite() && end.is_finite()) || end < start {
+            return Err(Box::new(SweepRangeError(format!("`{}` must be swept from a smaller value to a larger one", &caps[1]))))
+        }
+
+        // a small allowance keeps rounding error from dropping the last value
+        let n = ((end - start) / step + 1e-9).floor() + 1.0;
+        if n > MAX_CASES as f64 {
+            return Err(Box::new(SweepRangeError(format!("sweeping `{}` would make {n:e} cases, more than the limit of {MAX_CASES}", &caps[1]))))
+        }
+        let n = n as usize;
+
+        res.push((
+            caps.get(0).unwrap().start(), // keep track of where the sweep was found
+            caps[1].to_string(), 
+            (0..n).map(|i| start + i as f64 * step).collect()
+        ));
+    }
+
+    for caps in LIST.captures_iter(text) {
+        let vals = caps[2]
+            .split(',')
+            .map(|i| i.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()?;
+
+        res.push((caps.get(0).unwrap().start(), caps[1].to_string(), vals));
+    }
+
+    // report sweeps in the order they were written
+    res.sort_by_key(|i| i.0);
+
+    let cases = res.iter().fold(1_usize, |n, i| n.saturating_mul(i.2.len()));
+    if cases > MAX_CASES {
+        return Err(Box::new(SweepRangeError(format!("the sweeps would make {cases} cases, more than the limit of {MAX_CASES}"))))
+    }
+
+    Ok(res.into_iter().map(|i| (i.1, i.2)).collect())
+}
+
+/// Identifies and returns root choices found in a Nexsys-legal string. Roots are
+/// chosen with `root smallest for x`, `root largest for x`, or `root nearest 3.5 for x`.
+pub fn root_choices(text: &str) -> Result<HashMap<String, RootChoice>, Box<dyn Error>> {
+    lazy_static!{
+        static ref RE: Regex = Regex::new(r"(?im)^[ \t]*root (smallest|largest|nearest (\S+)) for ([a-z][a-z0-9_]*)[ \t]*$").unwrap();
+    }
+    let mut res = HashMap::new();
+
+    for caps in RE.captures_iter(text) {
+        let choice = match &caps[1] {
+            "smallest" => RootChoice::Smallest,
+            "largest" => RootChoice::Largest,
+            _ => RootChoice::Nearest(caps[2].parse()?)
+        };
+        res.insert(caps[3].to_string(), choice);
+    }
+    Ok(res)
+}
+
+/// Identifies and returns typical magnitudes found in a Nexsys-legal string. 
+/// Magnitudes are given with `typical 1e5 for p`, and are used to scale the variable.
+pub fn typical_magnitudes(text: &str) -> Result<HashMap<String, f64>, Box<dyn Error>> {
+    lazy_static!{
+        static ref RE: Regex = Regex::new(r"(?im)^[ \t]*typical (\S+) for ([a-z][a-z0-9_]*)[ \t]*$").unwrap();
+    }
+    let mut res = HashMap::new();
+
+    for caps in RE.captures_iter(text) {
+        res.insert(caps[2].to_string(), caps[1].parse()?);
+    }
+    Ok(res)
+}
+
+/// Returns the position that each equation in a Nexsys-legal string has once the string 
+/// is compiled, in the order the equations are written. The two branches of a conditional 
+/// are compiled into one equation, so they share a position.
+fn equation_positions(code: &str) -> Vec<usize> {
+    let text = comments(&OPTIONS.replace_all(code, ""));
+    let cdls: Vec<(usize, usize)> = CONDITIONAL.find_iter(&text).map(|i| (i.start(), i.end())).collect();
+
+    let mut res = vec![];
+    let mut count = 0;
+    let mut start = 0;
+
+    for line in text.split('\n') {
+        match cdls.iter().find(|(a, b)| (*a..*b).contains(&start)) {
+            Some(&(a, _)) => match text[a..start].matches('\n').count() {
+                0 => count += 1, // the `if` line, where the compiled equation goes
+                1 | 3 if line.contains('=') => res.push(count - 1),
+                _ => ()
+            },
+            None if line.contains('=') => {
+                res.push(count);
+                count += 1;
+            },
+            None => ()
+        }
+        start += line.len() + 1;
+    }
+    res
+}
+
+/// Identifies and returns the convergence criteria found in a Nexsys-legal string. 
+/// The norm is given with `norm 1`, `norm 2` or `norm inf`, and tolerances are given with 
+/// `tolerance relative 1e-8`, `tolerance step 1e-12`, `tolerance step 1e-6 for x` or 
+/// `tolerance 1e-3 for equation 2`, where equations are counted from 1 in the order they 
+/// are written (each branch of a conditional counting as one). The tolerances of equations 
+/// are stored in the order of the compiled system, so a conditional gets the smallest 
+/// tolerance of its branches. The criteria found are applied on top of `criteria`.
+pub fn convergence_criteria(text: &str, criteria: Convergence) -> Result<Convergence, Box<dyn Error>> {
+    lazy_static!{
+        static ref NORM: Regex = Regex::new(r"(?im)^[ \t]*norm (1|2|inf)[ \t]*$").unwrap();
+        static ref RELATIVE: Regex = Regex::new(r"(?im)^[ \t]*tolerance relative (\S+)[ \t]*$").unwrap();
+        static ref STEP: Regex = Regex::new(r"(?im)^[ \t]*tolerance step (\S+)( for ([a-z][a-z0-9_]*))?[ \t]*$").unwrap();
+        static ref EQUATION: Regex = Regex::new(r"(?im)^[ \t]*tolerance (\S+) for equation ([0-9]+)[ \t]*$").unwrap();
+    }
+    let mut res = criteria;
+
+    for caps in NORM.captures_iter(text) {
+        res.norm = match &caps[1] {
+            "1" => Norm::One,
+            "2" => Norm::Two,
+            _ => Norm::Infinity
+        };
+    }
+
+    for caps in RELATIVE.captures_iter(text) {
+        res.relative = Some(caps[1].parse()?);
+    }
+
+    for caps in STEP.captures_iter(text) {
+        let tolerance = caps[1].parse()?;
+        match caps.get(3) {
+            Some(var) => { res.variables.insert(var.as_str().to_string(), tolerance); },
+            None => res.step = Some(tolerance)
+        }
+    }
+
+    let positions = equation_positions(text);
+    let mut found: HashMap<usize, f64> = HashMap::new();
+
+    for caps in EQUATION.captures_iter(text) {
+        let i = match caps[2].parse::<usize>()?.checked_sub(1).and_then(|n| positions.get(n)) {
+            Some(&i) => i,
+            None => return Err(Box::new(ConvergenceFormatError(caps[0].trim().to_string())))
+        };
+        let tolerance: f64 = caps[1].parse()?;
+        found.entry(i).and_modify(|t| *t = t.min(tolerance)).or_insert(tolerance);
+    }
+
+    for (i, tolerance) in found {
+        if res.equations.len() <= i {
+            res.equations.resize(i + 1, None);
+        }
+        res.equations[i] = Some(tolerance);
+    }
+
+    Ok(res)
+}
+
+lazy_static!{
+    static ref OPTIONS: Regex = Regex::new(r"(?ims)^[ \t]*options[ \t]*\{(.*?)\}[ \t]*$").unwrap();
+}
+
+/// Identifies the `options` block in a Nexsys-legal string and returns `config` with 
+/// the options it gives. Each line of the block sets one option of `SolverConfig`, 
+/// using the same names as its fields, e.g.
+/// ```text
+/// options {
+///     tolerance: 1e-8
+///     update: good_broyden
+///     fallbacks: [homotopy, levenberg_marquardt]
+///     convergence.norm: infinity
+///     typical.p: 1e5
+/// }
+/// ```
+/// Nested options are reached with a `.`, and every other option is left as it is in `config`.
+pub fn options(text: &str, config: &SolverConfig) -> Result<SolverConfig, Box<dyn Error>> {
+    let mut res = serde_json::to_value(config)?;
+
+    for caps in OPTIONS.captures_iter(text) {
+        for line in comments(&caps[1]).lines().map(|i| i.trim()).filter(|i| !i.is_empty()) {
+            let (key, value) = match line.split_once(':') {
+                Some((k, v)) => (k.trim(), option_value(v.trim())),
+                None => return Err(Box::new(OptionsError(line.to_string())))
+            };
+
+            let path: Vec<&str> = key.split('.').collect();
+            if res.get(path[0]).is_none() {
+                return Err(Box::new(OptionsError(key.to_string())))
+            }
+
+            // create any missing objects along the way, e.g. for `multistart.starts`
+            let mut target = &mut res;
+            for k in path {
+                if !target.is_object() {
+                    *target = Value::Object(Map::new());
+                }
+                target = target.as_object_mut().unwrap().entry(k).or_insert(Value::Null);
+            }
+            *target = value;
+        }
+    }
+
+    // the monitor can't be written as an option, so it is kept as given
+    let mut res: SolverConfig = serde_json::from_value(res)?;
+    res.settings.monitor = config.settings.monitor.clone();
+    Ok(res)
+}
+
+/// Reads the value of an option as JSON if possible, or as a string (or list of 
+/// strings) otherwise, so that names like `good_broyden` don't need quotes.
+fn option_value(text: &str) -> Value {
+    if let Ok(v) = serde_json::from_str(text) {
+        return v
+    }
+    match text.strip_prefix('[').and_then(|i| i.strip_suffix(']')) {
+        Some(list) => Value::Array(
+            list.split(',')
+                .map(|i| i.trim())
+                .filter(|i| !i.is_empty())
+                .map(option_value)
+                .collect()
+        ),
+        None => Value::String(text.to_string())
+    }
+}
+
 /// Identifies and returns imports found in a Nexsys-legal string.
-fn _imports(text: &str, tolerance: Option<f64>, max_iterations: Option<usize>, allow_nonconvergence: bool) -> HashMap<String, Variable> {
+fn _imports(text: &str, config: &SolverConfig) -> HashMap<String, Variable> {
     lazy_static! {
         static ref RE: Regex = Regex::new(r"(?i)use [[a-z_\.-]+] ?-> ?.*").unwrap();
     }
@@ -125,7 +371,7 @@ fn _imports(text: &str, tolerance: Option<f64>, max_iterations: Option<usize>, a
         let clean_vars = cleanup!(raw_stmnt[1].to_string(), "->", ' ');
         let vars: Vec<&str> = clean_vars.split(',').collect();
 
-        let soln = solve(&contents, tolerance, max_iterations, allow_nonconvergence)
+        let soln = solve(&contents, config)
         .expect("failed to solve imported system").0
         .into_iter()
         .filter(move |i| vars.contains(&i.0.as_str()));
@@ -212,7 +458,7 @@ pub fn consts(text: &str) -> Result<String, Box<dyn Error>> {
 /// of the `.nxs`-formatted code
 pub fn compile(code: &str) -> Result<String, Box<dyn Error>> {
     
-    let mut nil = comments(code); 
+    let mut nil = comments(&OPTIONS.replace_all(code, "")); 
     
     nil = conversions(&nil)?;
     
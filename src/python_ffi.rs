@@ -1,95 +1,152 @@
 use std::collections::HashMap;
 use pyo3::{
     Python,
-    types::PyModule,
+    PyObject,
+    ToPyObject,
+    types::{PyModule, PyDict},
     PyResult,
+    exceptions::PyValueError,
     pymodule,
     pyclass,
     pymethods,
     pyfunction,
     wrap_pyfunction
 };
-use crate::{solver::Nexsys, algos::Variable, solve};
+use crate::{solver::{Nexsys, SolveStep}, algos::Variable, config::SolverConfig, solve};
+
+/// Converts the solver's procedure log into a list of Python `dict`s.
+fn py_log(py: Python, log: Vec<SolveStep>) -> PyResult<Vec<PyObject>> {
+    let mut res = vec![];
+    for step in log {
+        let d = PyDict::new(py);
+        d.set_item("equations", step.equations)?;
+        d.set_item("targets", step.targets)?;
+        d.set_item("method", step.method.to_string())?;
+        d.set_item("iterations", step.iterations)?;
+        d.set_item("residual", step.residual)?;
+        d.set_item("converged", step.converged)?;
+        d.set_item("elapsed", step.elapsed.as_secs_f64())?;
+        d.set_item("solutions", step.solutions)?;
+        d.set_item("at_bound", step.at_bound)?;
+        d.set_item("undetermined", step.undetermined)?;
+        if let Some(c) = step.criteria {
+            let criteria = PyDict::new(py);
+            criteria.set_item("residual", c.residual)?;
+            criteria.set_item("relative", c.relative)?;
+            criteria.set_item("growth", c.growth)?;
+            criteria.set_item("step", c.step)?;
+            criteria.set_item("equations", c.equations)?;
+            criteria.set_item("variables", c.variables)?;
+            d.set_item("criteria", criteria)?;
+        }
+        let mut history = vec![];
+        for i in step.history {
+            let iterate = PyDict::new(py);
+            iterate.set_item("iteration", i.iteration)?;
+            iterate.set_item("values", i.values)?;
+            iterate.set_item("residual", i.residual)?;
+            iterate.set_item("step", i.step)?;
+            iterate.set_item("condition", i.condition)?;
+            history.push(iterate.to_object(py));
+        }
+        d.set_item("history", history)?;
+        res.push(d.to_object(py));
+    }
+    Ok(res)
+}
+
+/// Reads solver options from keyword arguments, which have the same names 
+/// as the fields of `SolverConfig` (e.g. `tolerance = 1e-8`).
+fn py_config(py: Python, options: Option<&PyDict>) -> PyResult<SolverConfig> {
+    let options = match options {
+        Some(o) => o,
+        None => return Ok(SolverConfig::default())
+    };
+    let json: String = py.import("json")?.call_method1("dumps", (options,))?.extract()?;
+    serde_json::from_str(&json).map_err(|e| PyValueError::new_err(e.to_string()))
+}
 
 /// The Python-accessible Nexsys solver object.
 #[pyclass(name = "Nexsys")]
 pub struct PyNexsys {
-    system: Option<Nexsys>
+    system: Nexsys
 }
 #[pymethods]
 impl PyNexsys {
     /// Instantiates a new Nexsys object in Python (a.k.a. `__init__`)
     #[new]
-    #[pyo3(signature = (text, tol = 1E-10, limit = 300, nonconvergence = false))]
-    fn new(text: &str, tol: f64, limit: usize, nonconvergence: bool) -> PyResult<PyNexsys> {
-        Ok(PyNexsys {
-            system: Some(Nexsys::new(text, tol, limit, nonconvergence))
-        })
+    #[pyo3(signature = (text, **options))]
+    fn new(py: Python, text: &str, options: Option<&PyDict>) -> PyResult<PyNexsys> {
+        match Nexsys::new(text, py_config(py, options)?) {
+            Ok(system) => Ok(PyNexsys { system }),
+            Err(e) => Err(PyValueError::new_err(e.to_string()))
+        }
     }
 
     /// Manually inserts a value into the system solution. This can be 
     /// used to parametrize Nexsys code in a way that is more 
     /// accessible to another program.
     pub fn edit(&mut self, var: &str, value: f64) {
-        if let Some(n) = &mut self.system {
-            n.edit(var, value);
-        }
+        self.system.edit(var, value);
     } 
 
+    /// Removes a value that was previously given by `Nexsys.edit()`.
+    pub fn remove_edit(&mut self, var: &str) {
+        self.system.remove_edit(var);
+    }
+
     /// Does the same thing as `Nexsys.edit()` but adds a `HashMap` of variables all at the same time.
     pub fn mass_add_edits(&mut self, values: HashMap<String, f64>) {
         let vals = values.into_iter()
         .map(|i| (i.0, Variable::new(i.1, None)))
         .collect();
-        if let Some(n) = &mut self.system {
-            n.mass_add_edits(vals);
-        }
+        self.system.mass_add_edits(vals);
     }
 
     /// Specifies an initial guess value for the given variable
     pub fn guess(&mut self, var: &str, value: f64) {
-        if let Some(n) = &mut self.system {
-            n.guess(var, value);
-        }
+        self.system.guess(var, value);
     }
 
     /// Does the same thing as `Nexsys.guess()` but adds a `HashMap` of guess values all at the same time.
     pub fn mass_add_guess(&mut self, guesses: HashMap<String, f64>) {
-        if let Some(n) = &mut self.system {
-            n.mass_add_guess(guesses);
-        }
+        self.system.mass_add_guess(guesses);
     }
 
     /// Adds a domain specification for the given variable.
     pub fn domain(&mut self, var: &str, value: Vec<f64>) {
-        if let Some(n) = &mut self.system {
-            n.domain(var, [value[0], value[1]]);
-        }
+        self.system.domain(var, [value[0], value[1]]);
     }
 
     /// Does the same thing as `Nexsys.domain()` but adds a `HashMap` of domains all at the same time.
     pub fn mass_add_domains(&mut self, domains: HashMap<String, [f64; 2]>) {
-        if let Some(n) = &mut self.system {
-            n.mass_add_domains(domains);
-        }
+        self.system.mass_add_domains(domains);
+    }
+
+    /// Specifies the tolerance on the residual of the `i`th equation of the system.
+    pub fn equation_tolerance(&mut self, i: usize, tolerance: f64) {
+        self.system.equation_tolerance(i, tolerance);
+    }
+
+    /// Specifies the tolerance on the step of the given variable.
+    pub fn step_tolerance(&mut self, var: &str, tolerance: f64) {
+        self.system.step_tolerance(var, tolerance);
     }
 
-    /// Solves the equations passed to the Nexsys solver, consuming the `self` value and 
-    /// returning the solution to the system as a `dict`. This method can only be called once.
-    /// If called more than once on the same instance of the object in Python, it will crash.
-    pub fn solve(&mut self) -> PyResult<(HashMap<String, f64>, Vec<String>)> {
+    /// Solves the equations passed to the Nexsys solver, returning the solution to the 
+    /// system as a `dict`. This method can be called again after changing edits, guesses
+    /// or domains to re-solve the same system.
+    pub fn solve(&mut self, py: Python) -> PyResult<(HashMap<String, f64>, Vec<PyObject>)> {
         
-        let opn = self.system.take(); 
-        let n = opn.unwrap();
-        let mut res = match n.solve() {
+        let mut res = match self.system.solve() {
             Ok(o) => o,
-            Err(e) => panic!("{}", e)
+            Err(e) => return Err(PyValueError::new_err(e.to_string()))
         };
 
         let soln = res.0.drain().map(
             |i| (i.0, i.1.as_f64())
         ).collect::<HashMap<String, f64>>();
-        let log = res.1;
+        let log = py_log(py, res.1)?;
 
         Ok((soln, log))
     }
@@ -97,17 +154,17 @@ impl PyNexsys {
 
 /// The Python-accessible Nexsys interpreter function
 #[pyfunction]
-#[pyo3(signature = (system, tolerance = 1E-10, max_iterations = 300, allow_nonconvergence = false))]
-pub fn py_solve(system: &str, tolerance: f64, max_iterations: usize, allow_nonconvergence: bool) -> PyResult<(HashMap<String, f64>, Vec<String>)> {
-    match solve(system, Some(tolerance), Some(max_iterations), allow_nonconvergence) {
+#[pyo3(signature = (system, **options))]
+pub fn py_solve(py: Python, system: &str, options: Option<&PyDict>) -> PyResult<(HashMap<String, f64>, Vec<PyObject>)> {
+    match solve(system, &py_config(py, options)?) {
         Ok(o) => {
             let (soln, log) = o;
 
             let pythonic = soln.into_iter().map(|i| (i.0, i.1.as_f64())).collect();
 
-            Ok((pythonic, log))
+            Ok((pythonic, py_log(py, log)?))
         },
-        Err(e) => panic!("{}", e)
+        Err(e) => Err(PyValueError::new_err(e.to_string()))
     }
 }
 
@@ -1,5 +1,9 @@
-use std::{collections::HashMap, error::Error};
-use crate::{algos::*, parsing::legal_variable, errors::SolverConvergenceError, SolverOutput};
+use std::{collections::HashMap, error::Error, fmt::{self, Display}, time::{Duration, Instant}};
+use crate::{
+    algos::*, config::SolverConfig, expr::Expr, sweep::Table, SolverOutput,
+    errors::{NoSignChangeError, SolverCancelledError, SolverConstraintError, SolverConvergenceError, SolverTimeoutError, TimeLimitError},
+    monitor::{Monitor, is_interruption}
+};
 
 #[derive(Clone)]
 #[derive(Debug)]
@@ -11,6 +15,115 @@ enum Progress {
     NoneSolved
 }
 
+/// A record of one step taken by the Nexsys solver while solving a system.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct SolveStep {
+    /// The equations that were solved in this step.
+    pub equations: Vec<String>,
+    /// The variables that were solved for in this step.
+    pub targets: Vec<String>,
+    /// The algorithm used in this step.
+    pub method: Method,
+    /// The number of iterations the algorithm took.
+    pub iterations: usize,
+    /// The residual of the equations at the end of this step.
+    pub residual: f64,
+    /// Whether or not the algorithm converged.
+    pub converged: bool,
+    /// The time spent on this step.
+    pub elapsed: Duration,
+    /// Every distinct solution that was found for the targets when several were
+    /// searched for (see `Settings::multistart`), otherwise empty.
+    pub solutions: Vec<HashMap<String, f64>>,
+    /// The targets that ended on a bound of their domain (see `Variable::at_bound`).
+    pub at_bound: Vec<String>,
+    /// The targets that the Jacobian couldn't determine at some point during this 
+    /// step because it was rank-deficient (see `Report::undetermined`).
+    pub undetermined: Vec<String>,
+    /// The values that the convergence criteria reached in this step, if the 
+    /// algorithm measures them (see `Report::criteria`).
+    pub criteria: Option<Criteria>,
+    /// The state after each iteration of this step, if `Settings::history` is on and 
+    /// the algorithm records it (see `Report::history`).
+    pub history: Vec<Iterate>
+}
+impl Display for SolveStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, 
+            "{} {} for {} via {} ({} iterations, residual {:e}, {:?}){}{}{}",
+            if self.converged { "solved" } else { "timeout while solving" },
+            self.equations.join(", "),
+            self.targets.join(", "),
+            self.method,
+            self.iterations,
+            self.residual,
+            self.elapsed,
+            if self.solutions.len() > 1 { format!(" [{} solutions found]", self.solutions.len()) } else { String::new() },
+            if self.at_bound.is_empty() { String::new() } else { format!(" [on a domain bound: {}]", self.at_bound.join(", ")) },
+            if self.undetermined.is_empty() { String::new() } else { format!(" [rank-deficient jacobian, undetermined: {}]", self.undetermined.join(", ")) }
+        )
+    }
+}
+
+/// Shorthand for the result of solving a block of equations.
+type BlockResult<'a> = Result<Report<HashMap<&'a str, Variable>>, Box<dyn Error>>;
+
+/// Solves a block of equations with the given method, or returns `None` if the 
+/// method can't solve systems of equations.
+fn solve_block<'a>(method: Method, system: &[Expr], guess: HashMap<&'a str, Variable>, settings: &Settings) -> Option<BlockResult<'a>> {
+    match method {
+        Method::MvNewton => Some(mv_newton_raphson(system, guess, settings)),
+        Method::Broyden => Some(broyden(system, guess, settings)),
+        Method::Homotopy => Some(homotopy(system, guess, settings)),
+        Method::LevenbergMarquardt => Some(levenberg_marquardt(system, guess, settings)),
+        _ => None
+    }
+}
+
+/// Collects the history of every step in a solver log (see `Settings::history`) into a 
+/// table with one row per iteration, e.g. for plotting. The `step` column is the index of 
+/// the step in the log and `step_norm` is the length of the iteration's step. Variables 
+/// that weren't unknowns of a step are `NaN` in its rows.
+/// # Example
+/// ```
+/// use nexsys::{solve, config::SolverConfig, solver::history_table};
+///
+/// let config = SolverConfig::builder().history(true).build();
+/// let (_, log) = solve("x^2 + y = 11\nx + y^2 = 7", &config).unwrap();
+/// let table = history_table(&log);
+///
+/// assert_eq!(table.rows.len(), log[0].iterations);
+/// assert!(table.column("condition").unwrap().iter().all(|i| i.is_finite()));
+/// ```
+pub fn history_table(log: &[SolveStep]) -> Table {
+    let mut vars: Vec<String> = log.iter()
+        .flat_map(|i| i.history.iter().flat_map(|j| j.values.keys().cloned()))
+        .collect();
+    vars.sort();
+    vars.dedup();
+
+    let columns = ["step", "iteration", "residual", "step_norm", "condition"];
+    let mut table = Table::new(columns.iter().map(|i| i.to_string()).chain(vars.iter().cloned()).collect());
+
+    for (n, step) in log.iter().enumerate() {
+        for i in &step.history {
+            let mut row = vec![n as f64, i.iteration as f64, i.residual, i.step, i.condition];
+            row.extend(vars.iter().map(|v| i.values.get(v).copied().unwrap_or(f64::NAN)));
+            table.rows.push(row);
+        }
+    }
+    table
+}
+
+/// Adds a step to the solver's log and reports it to the observer, if there is one.
+fn record(log: &mut Vec<SolveStep>, monitor: &Monitor, step: SolveStep) {
+    monitor.step(&step);
+    log.push(step);
+}
+
 #[derive(Clone)]
 #[derive(Debug)]
 #[derive(PartialEq)]
@@ -18,40 +131,46 @@ enum Progress {
 /// Access point to the Nexsys equation solver engine
 pub struct Nexsys {
     equations : Vec<Equation>,
+    edits: HashMap<String, Variable>,
     guesses: HashMap<String, f64>,
     domains: HashMap<String, [f64; 2]>,
+    roots: HashMap<String, RootChoice>,
     solution: HashMap<String, Variable>,
-    log: Vec<String>,
-    tolerance: f64,
-    max_iterations: usize,
-    allow_nonconvergence: bool
+    log: Vec<SolveStep>,
+    config: SolverConfig
 }
 impl Nexsys {
     /// Initializes a new Nexsys solver from a string.
     /// 
     /// The `Nexsys` struct is used for processing equations given in
     /// string format. This enables solving systems from text files
-    /// or string data passed from other programs.
-    pub fn new(text: &str, tolerance: f64, max_iterations: usize, allow_nonconvergence: bool) -> Nexsys {
+    /// or string data passed from other programs. An error is returned
+    /// if any of the equations can't be parsed.
+    pub fn new(text: &str, config: SolverConfig) -> Result<Nexsys, Box<dyn Error>> {
 
         let equations = text.split('\n')
         .filter(|i| i.contains('='))
         .map(Equation::new)
-        .collect();
+        .collect::<Result<Vec<Equation>, _>>()?;
 
+        let edits = HashMap::new();
         let guesses = HashMap::new();
         let domains = HashMap::new();
+        let roots = HashMap::new();
         let solution = HashMap::new();
         let log = vec![];
 
-        Nexsys { equations, guesses, domains, solution, log, tolerance, max_iterations, allow_nonconvergence }
+        Ok(Nexsys { equations, edits, guesses, domains, roots, solution, log, config })
     }
 
     /// Manually inserts a value into the system solution. This can be 
     /// used to parametrize Nexsys code in a way that is more 
     /// accessible to another program.
+    /// 
+    /// Edits are kept between calls to `Nexsys.solve()`, so they can be 
+    /// changed to re-solve the same system with different inputs.
     pub fn edit(&mut self, var: &str, value: f64) {
-        self.solution.insert(
+        self.edits.insert(
             var.to_string(), 
             Variable::new(
                 value, 
@@ -62,7 +181,12 @@ impl Nexsys {
 
     /// Does the same thing as `Nexsys.edit()` but adds a `HashMap` of variables all at the same time.
     pub fn mass_add_edits(&mut self, values: HashMap<String, Variable>) {
-        self.solution.extend(values.into_iter());
+        self.edits.extend(values);
+    }
+
+    /// Removes a value that was previously given by `Nexsys.edit()`.
+    pub fn remove_edit(&mut self, var: &str) {
+        self.edits.remove(var);
     }
 
     /// Specifies an initial guess value for the given variable
@@ -72,7 +196,7 @@ impl Nexsys {
 
     /// Does the same thing as `Nexsys.guess()` but adds a `HashMap` of guess values all at the same time.
     pub fn mass_add_guess(&mut self, guesses: HashMap<String, f64>) {
-        self.guesses.extend(guesses.into_iter());
+        self.guesses.extend(guesses);
     }
 
     /// Adds a domain specification for the given variable.
@@ -82,22 +206,92 @@ impl Nexsys {
 
     /// Does the same thing as `Nexsys.domain()` but adds a `HashMap` of domains all at the same time.
     pub fn mass_add_domains(&mut self, domains: HashMap<String, [f64; 2]>) {
-        self.domains.extend(domains.into_iter());
+        self.domains.extend(domains);
     }
 
-    /// Sub in known variables to simplify an expression
-    fn substitute(&self, mut expr: String) -> String {
-        
-        let vars = legal_variable(&expr);
-        let knowns = self.solution.iter().filter(|&i| vars.contains(i.0));
+    /// Specifies which root should be used for the given variable when it is solved 
+    /// from a single equation. Every root in the variable's domain is found, so a 
+    /// domain must also be given.
+    pub fn root(&mut self, var: &str, choice: RootChoice) {
+        self.roots.insert(var.to_string(), choice);
+    }
 
-        for v in knowns {
-            expr = expr.replace(
-                v.0, 
-                &format!("({})", v.1.as_f64())
-            );
+    /// Does the same thing as `Nexsys.root()` but adds a `HashMap` of root choices all at the same time.
+    pub fn mass_add_roots(&mut self, roots: HashMap<String, RootChoice>) {
+        self.roots.extend(roots);
+    }
+
+    /// Limits how far the given variable may move in a single Newton step.
+    pub fn max_step(&mut self, var: &str, cap: f64) {
+        self.config.settings.max_step.insert(var.to_string(), cap);
+    }
+
+    /// Specifies the typical magnitude of the given variable, which is used to scale it 
+    /// during Newton iterations in place of its guess value or domain.
+    pub fn typical(&mut self, var: &str, magnitude: f64) {
+        self.config.settings.typical.insert(var.to_string(), magnitude);
+    }
+
+    /// Does the same thing as `Nexsys.typical()` but adds a `HashMap` of magnitudes all at the same time.
+    pub fn mass_add_typical(&mut self, magnitudes: HashMap<String, f64>) {
+        self.config.settings.typical.extend(magnitudes);
+    }
+
+    /// Specifies the tolerance on the residual of the `i`th equation of the system, counting 
+    /// from 0 in the order of the text given to `Nexsys::new`. This equation is then left 
+    /// out of the norm that is compared to the tolerance.
+    pub fn equation_tolerance(&mut self, i: usize, tolerance: f64) {
+        let equations = &mut self.config.settings.convergence.equations;
+        if equations.len() <= i {
+            equations.resize(i + 1, None);
         }
-        expr
+        equations[i] = Some(tolerance);
+    }
+
+    /// Specifies the tolerance on the step of the given variable, see `Convergence::variables`.
+    pub fn step_tolerance(&mut self, var: &str, tolerance: f64) {
+        self.config.settings.convergence.variables.insert(var.to_string(), tolerance);
+    }
+
+    /// Replaces the criteria used to decide when the solving algorithms have converged.
+    pub fn convergence(&mut self, criteria: Convergence) {
+        self.config.settings.convergence = criteria;
+    }
+
+    /// Replaces the settings used by the solving algorithms.
+    pub fn configure(&mut self, settings: Settings) {
+        self.config.settings = settings;
+    }
+
+    /// Returns the settings used by the solving algorithms.
+    pub fn settings(&self) -> &Settings {
+        &self.config.settings
+    }
+
+    /// Replaces all of the options used to solve the system.
+    pub fn set_config(&mut self, config: SolverConfig) {
+        self.config = config;
+    }
+
+    /// Returns the options used to solve the system.
+    pub fn config(&self) -> &SolverConfig {
+        &self.config
+    }
+
+    /// Returns the settings used to solve the equations at the given positions in the 
+    /// system, with the tolerances of those equations (see `Convergence::equations`) 
+    /// given in the same order.
+    fn settings_for(&self, rows: &[usize]) -> Settings {
+        let mut settings = self.config.settings.clone();
+        settings.convergence.equations = rows.iter()
+            .map(|&i| self.config.settings.convergence.equation(i))
+            .collect();
+        settings
+    }
+
+    /// Sub in known variables to simplify an expression
+    fn substitute(&self, expr: &Expr) -> Expr {
+        expr.substitute(&|v| self.solution.get(v).map(|i| i.as_f64()))
     }
 
     /// Solve any 1-unknown equations in the system.
@@ -105,7 +299,7 @@ impl Nexsys {
 
         let mut solved = 0;
 
-        for eqn in &self.equations {
+        for (row, eqn) in self.equations.iter().enumerate() {
             if eqn.n_unknowns(&self.solution) != 1 { 
                 continue // only operate on 1-unknown problems
             }
@@ -115,45 +309,158 @@ impl Nexsys {
             let target = &eqn.unknowns(&self.solution)[0];
 
             // obtain guess domain and value
-            let v = Variable::new( 
+            let mut v = Variable::new( 
                 match self.guesses.get(target) {
                     Some(&val) => val,
-                    None => 1.0
+                    None => self.config.default_guess
                 },
                 self.domains
                     .get(target)
                     .copied()
             );
 
-            let expr = self.substitute(eqn.as_expr());
-            let ans = match newton_raphson(
-                &expr, 
-                (target, v.clone()), 
-                self.tolerance, 
-                self.max_iterations
-            )? {
-                Solution::Converged(c) => Solution::Converged(c),
-                Solution::NonConverged(_) => {
-                    self.log.push("N.R. method did not converge. Attempted G.S.S. method instead.".to_string());
-                    golden_search( // Try the golden search algorithm if newton's fails
-                        &expr, 
-                        (target, v), 
-                        self.tolerance
-                    )? // if golden search fails, throw an error
+            let expr = self.substitute(eqn.expr());
+            let text = vec![eqn.as_text().trim().to_string()];
+
+            self.config.settings.monitor.check()?;
+            self.config.settings.monitor.start(&text, std::slice::from_ref(target));
+
+            let clock = Instant::now();
+            if let Some(ans) = direct(&expr, (target, v.clone()), self.config.settings.tolerance) {
+                if let Solution::Converged(o) = ans.solution {
+                    self.solution.insert(o.0.to_string(), o.1);
+                    record(&mut self.log, &self.config.settings.monitor, SolveStep {
+                        equations: text,
+                        targets: vec![target.to_string()],
+                        method: Method::Direct,
+                        iterations: 0,
+                        residual: ans.residual,
+                        converged: true,
+                        elapsed: clock.elapsed(),
+                        solutions: vec![],
+                        at_bound: vec![],
+                        undetermined: vec![],
+                        criteria: None,
+                        history: vec![]
+                    });
+                    continue
                 }
+
+                record(&mut self.log, &self.config.settings.monitor, SolveStep {
+                    equations: text.clone(),
+                    targets: vec![target.to_string()],
+                    method: Method::Direct,
+                    iterations: 0,
+                    residual: ans.residual,
+                    converged: false,
+                    elapsed: clock.elapsed(),
+                    solutions: vec![],
+                    at_bound: vec![],
+                    undetermined: vec![],
+                    criteria: None,
+                    history: vec![]
+                });
+
+                // polish the direct solution (e.g. to remove rounding error) numerically
+                v = ans.unwrap().1;
+            }
+
+            if let Some(choice) = self.roots.get(target) {
+                let found = all_roots(&expr, (target, v.clone()), &self.config.settings)?;
+                let (iterations, residual) = (found.iterations, found.residual);
+
+                match choice.select(&found.unwrap()) {
+                    Some(x) => {
+                        v.change(x);
+                        self.solution.insert(target.to_string(), v);
+                        record(&mut self.log, &self.config.settings.monitor, SolveStep {
+                            equations: text,
+                            targets: vec![target.to_string()],
+                            method: Method::AllRoots,
+                            iterations,
+                            residual,
+                            converged: true,
+                            elapsed: clock.elapsed(),
+                            solutions: vec![],
+                            at_bound: vec![],
+                            undetermined: vec![],
+                            criteria: None,
+                            history: vec![]
+                        });
+                        continue
+                    },
+                    None => return Err(Box::new(NoSignChangeError(expr.to_string())))
+                }
+            }
+
+            let mut method = Method::Newton;
+            let settings = self.settings_for(&[row]);
+
+            let ans = match newton_raphson(&expr, (target, v.clone()), &settings) {
+                Ok(ans) if ans.converged() => ans,
+                Err(e) if is_interruption(e.as_ref()) => return Err(e),
+                newton if !self.config.fallbacks.contains(&Method::Brent) => newton?,
+                newton => match brent(&expr, (target, v), &settings) {
+                    Ok(ans) => {
+                        if let Ok(newton) = newton {
+                            record(&mut self.log, &self.config.settings.monitor, SolveStep {
+                                equations: text.clone(),
+                                targets: vec![target.to_string()],
+                                method,
+                                iterations: newton.iterations,
+                                residual: newton.residual,
+                                converged: false,
+                                elapsed: clock.elapsed(),
+                                solutions: vec![],
+                                at_bound: vec![],
+                                undetermined: vec![],
+                                criteria: newton.criteria,
+                                history: newton.history
+                            });
+                        }
+
+                        // Use brent's method if newton's fails
+                        method = Method::Brent;
+                        ans
+                    },
+                    Err(e) => match newton {
+                        Ok(newton) if self.config.allow_nonconvergence => newton, // keep newton's best attempt
+                        _ => return Err(e)
+                    }
+                }
+            };
+
+            let mut step = SolveStep {
+                equations: text,
+                targets: vec![target.to_string()],
+                method,
+                iterations: ans.iterations,
+                residual: ans.residual,
+                converged: ans.converged(),
+                elapsed: clock.elapsed(),
+                solutions: vec![],
+                at_bound: vec![],
+                undetermined: vec![],
+                criteria: ans.criteria,
+                history: ans.history
             };
 
-            match ans {
+            match ans.solution {
                 Solution::Converged(o) => {
                     self.solution.insert(o.0.to_string(), o.1);
-                    self.log.push(format!("solved {} for variable {}", eqn.as_text().replace('\r', ""), target));
+                    record(&mut self.log, &self.config.settings.monitor, step);
                 },
                 Solution::NonConverged(e) => {
-                    if self.allow_nonconvergence {
+                    if self.config.allow_nonconvergence {
                         self.solution.insert(e.0.to_string(), e.1);
-                        self.log.push(format!("timeout while solving {} for variable {}", eqn.as_text().replace('\r', ""), target));
+                        record(&mut self.log, &self.config.settings.monitor, step);
                     } else {
-                        return Err(Box::new(SolverConvergenceError))
+                        // the failed values aren't kept, so their bounds are checked here
+                        if e.1.at_bound() {
+                            step.at_bound = step.targets.clone();
+                        }
+                        record(&mut self.log, &self.config.settings.monitor, step);
+                        return Err(Box::new(SolverConvergenceError::default()))
                     }
                 }
             }
@@ -169,38 +476,33 @@ impl Nexsys {
     /// Identify, group, and solve properly constrained systems of equations in the system.
     fn heavy_work(&mut self) -> Result<Progress, Box<dyn Error>> {
         
-        let mut blks = BlockMgr::new(&self.solution);
+        let mut graph = Incidence::new(&self.solution);
 
         for eqn in &self.equations {
-            blks.add_item(eqn);
+            graph.add_item(eqn);
         }
 
-        let blocks = blks.constrained();
+        let blocks = graph.constrained();
         
         if blocks.is_none() {
             return Ok(Progress::NoneSolved)
         }
 
-        for block in blocks.unwrap() {
+        for (i, block) in blocks.unwrap().into_iter().enumerate() {
 
-            // Lord forgive me for what I am about to do...
-            let preprocess: Vec<String> = block.1.iter().map(
-
-                |i| self.substitute(i.to_string())                  // Put this in memory for later reference
+            if block.vars.len() == 1 && i > 0 {
+                break; // 1-unknown equations are left for `light_work`
+            }
 
-            ).collect(); 
-            let system: Vec<&str> = preprocess.iter().map(
-                
-                |i| i.as_str()                                      // Create &str's that reference preprocess
-                
-            ).collect(); 
+            let system: Vec<Expr> = block.eqns.iter().map(|i| self.substitute(i.expr())).collect();
+            let settings = self.settings_for(&block.rows);
             
             // Build guess vector
             let mut guess = HashMap::new();
-            for v in &block.0 {
+            for v in &block.vars {
 
                 let k = v.as_str();
-                let mut value = 1.0;
+                let mut value = self.config.default_guess;
                 let mut domain = None;
 
                 // get guess val if present
@@ -217,27 +519,106 @@ impl Nexsys {
             }
 
             // Solve system and report status of solution
-            let err_msg = format!("timeout solving system {:#?} for variables {:?}", block.1, guess.keys());
-            let msg = format!("solved system {:#?} for variables {:?}", block.1, guess.keys());
-            let ans = mv_newton_raphson(system, guess, self.tolerance, self.max_iterations)?;
+            let clock = Instant::now();
+            let text: Vec<String> = block.eqns.iter().map(|i| i.as_text().trim().to_string()).collect();
+            let mut solutions = vec![];
+
+            settings.monitor.check()?;
+            settings.monitor.start(&text, &block.vars);
+
+            let found = match &settings.multistart {
+                Some(options) if block.vars.iter().all(|v| self.domains.contains_key(v)) => {
+                    Some(multistart(&system, guess.clone(), options, &settings)?)
+                },
+                _ => None
+            };
+
+            let (mut method, mut ans) = match found {
+                Some(found) if found.converged() => {
+                    let Report { solution, iterations, residual, undetermined, criteria, history } = found;
+                    let mut all = solution.unwrap();
+                    solutions = all.iter()
+                        .map(|i| i.iter().map(|(k, v)| (k.to_string(), v.as_f64())).collect())
+                        .collect();
+                    (Method::Multistart, Ok(Report { solution: Solution::Converged(all.remove(0)), iterations, residual, undetermined, criteria, history }))
+                },
+                _ => match settings.update {
+                    JacobianUpdate::Full => (Method::MvNewton, mv_newton_raphson(&system, guess.clone(), &settings)),
+                    _ => (Method::Broyden, broyden(&system, guess.clone(), &settings))
+                }
+            };
+
+            let failed = |method: Method, ans: &Report<HashMap<&str, Variable>>| SolveStep {
+                equations: text.clone(),
+                targets: block.vars.clone(),
+                method,
+                iterations: ans.iterations,
+                residual: ans.residual,
+                converged: false,
+                elapsed: clock.elapsed(),
+                solutions: vec![],
+                at_bound: vec![],
+                undetermined: ans.undetermined.clone(),
+                criteria: ans.criteria.clone(),
+                history: ans.history.clone()
+            };
+
+            // Try each fallback from the original guess in turn until one converges
+            for &fallback in &self.config.fallbacks {
+                match &ans {
+                    Ok(ans) if ans.converged() => break,
+                    Err(e) if is_interruption(e.as_ref()) => break,
+                    _ => ()
+                }
+                let Some(next) = solve_block(fallback, &system, guess.clone(), &settings) else {
+                    continue // e.g. brent's method, which only solves single equations
+                };
+                if let Ok(ans) = &ans {
+                    record(&mut self.log, &self.config.settings.monitor, failed(method, ans));
+                }
+                method = fallback;
+                ans = next;
+            }
+            let ans = ans?;
+
+            let mut step = SolveStep {
+                equations: text,
+                targets: block.vars.clone(),
+                method,
+                iterations: ans.iterations,
+                residual: ans.residual,
+                converged: ans.converged(),
+                elapsed: clock.elapsed(),
+                solutions,
+                at_bound: vec![],
+                undetermined: ans.undetermined,
+                criteria: ans.criteria,
+                history: ans.history
+            };
             
-            match ans {
+            match ans.solution {
                 Solution::Converged(s) => {
-                    self.log.push(msg.replace(['[', ']', '"', '\r'], "")); // Add solver report to log
+                    record(&mut self.log, &self.config.settings.monitor, step); // Add solver report to log
                     
                     self.solution.extend(s.into_iter().map(
                         |i| (i.0.to_string(), i.1)
                     ));
                 },
                 Solution::NonConverged(s) => {
-                    if self.allow_nonconvergence {
-                        self.log.push(err_msg.replace(['[', ']', '"', '\r'], "")); // Add solver report to log
+                    if self.config.allow_nonconvergence {
+                        record(&mut self.log, &self.config.settings.monitor, step); // Add solver report to log
                         
                         self.solution.extend(s.into_iter().map(
                             |i| (i.0.to_string(), i.1)
                         ));
                     } else {
-                        return Err(Box::new(SolverConvergenceError))
+                        // the failed values aren't kept, so their bounds are checked here
+                        step.at_bound = step.targets.iter()
+                            .filter(|i| s.get(i.as_str()).is_some_and(|v| v.at_bound()))
+                            .cloned()
+                            .collect();
+                        record(&mut self.log, &self.config.settings.monitor, step);
+                        return Err(Box::new(SolverConvergenceError::default()))
                     }
                 }      
             }  
@@ -246,9 +627,82 @@ impl Nexsys {
         Ok(Progress::Solved)
     }
 
-    /// Solves the equations passed to the Nexsys solver, consuming the `self` value and 
-    /// returning the solution to the system as a `HashMap`.
-    pub fn solve(mut self) -> Result<SolverOutput, Box<dyn Error>> {
+    /// Returns the solution if no unknowns are left in the system, or a diagnosis of 
+    /// the remaining equations if there are.
+    fn finish(&mut self) -> Result<SolverOutput, Box<dyn Error>> {
+        self.mark_bounds();
+
+        if self.equations.iter().all(|i| i.n_unknowns(&self.solution) == 0) {
+            return Ok((self.solution.clone(), self.log.clone()))
+        }
+
+        let mut graph = Incidence::new(&self.solution);
+
+        for eqn in &self.equations {
+            graph.add_item(eqn);
+        }
+
+        Err(Box::new(graph.diagnose()))
+    }
+
+    /// Records which targets of each step in the log ended up on a bound of their domain.
+    /// Steps whose values weren't kept in the solution are left as they are.
+    fn mark_bounds(&mut self) {
+        let solution = &self.solution;
+        for step in self.log.iter_mut().filter(|i| i.targets.iter().all(|t| solution.contains_key(t))) {
+            step.at_bound = step.targets.iter()
+                .filter(|i| solution[*i].at_bound())
+                .cloned()
+                .collect();
+        }
+    }
+
+    /// Solves the equations passed to the Nexsys solver, returning the solution to 
+    /// the system as a `HashMap`.
+    /// 
+    /// Only the solution and log are reset between calls, so the same `Nexsys` can be 
+    /// edited and solved as many times as needed.
+    /// 
+    /// If the solver stalls with unknowns left in the system, a `SolverConstraintError`
+    /// describing the remaining equations is returned instead. It carries the log of the 
+    /// steps taken, as does the `SolverConvergenceError` returned when a step doesn't 
+    /// converge (and non-convergence isn't allowed). If the solve is cancelled 
+    /// or runs out of time (see `SolverConfig::time_limit`), a `SolverCancelledError` or 
+    /// `SolverTimeoutError` carrying the values solved for so far is returned.
+    pub fn solve(&mut self) -> Result<SolverOutput, Box<dyn Error>> {
+        self.solution = self.edits.clone();
+        self.log.clear();
+
+        let monitor = self.config.settings.monitor.clone();
+        if let Some(limit) = self.config.time_limit {
+            if !limit.is_finite() || limit < 0.0 {
+                return Err(Box::new(TimeLimitError(limit)))
+            }
+            // a limit too long to fit in a `Duration` is no limit at all
+            if let Ok(limit) = Duration::try_from_secs_f64(limit) {
+                self.config.settings.monitor = monitor.clone().time_limit(limit);
+            }
+        }
+        let result = self.work();
+        self.config.settings.monitor = monitor;
+
+        result.map_err(|mut e| {
+            if let Some(e) = e.downcast_mut::<SolverCancelledError>() {
+                e.solution = self.solution.clone();
+            } else if let Some(e) = e.downcast_mut::<SolverTimeoutError>() {
+                e.solution = self.solution.clone();
+            } else if let Some(e) = e.downcast_mut::<SolverConvergenceError>() {
+                self.mark_bounds();
+                e.log = self.log.clone();
+            } else if let Some(e) = e.downcast_mut::<SolverConstraintError>() {
+                e.log = self.log.clone(); // bounds were marked by `finish()`
+            }
+            e
+        })
+    }
+
+    /// Does the work for `Nexsys.solve()` until the system is solved or the solver stalls.
+    fn work(&mut self) -> Result<SolverOutput, Box<dyn Error>> {
         loop {
             match self.light_work()? {
                 Progress::Solved => {
@@ -260,11 +714,133 @@ impl Nexsys {
                             continue;
                         },
                         Progress::NoneSolved => {
-                            return Ok((self.solution, self.log))
+                            return self.finish()
                         }
                     }
                 }
             }
         }
     }
+
+    /// Solves the system once for each case in `cases`, returning a table of the 
+    /// swept variables and the requested `outputs`. If no outputs are given, every 
+    /// variable in the solution is reported.
+    /// 
+    /// Each case is warm-started from the previous case's solution, and the edits 
+    /// and guesses given before the sweep are restored once it is finished.
+    pub fn sweep(&mut self, cases: &[HashMap<String, f64>], outputs: &[&str]) -> Result<Table, Box<dyn Error>> {
+        let edits = self.edits.clone();
+        let guesses = self.guesses.clone();
+
+        let res = self.sweep_cases(cases, outputs);
+
+        self.edits = edits;
+        self.guesses = guesses;
+
+        res
+    }
+
+    /// Does the work for `Nexsys.sweep()` without restoring edits and guesses.
+    fn sweep_cases(&mut self, cases: &[HashMap<String, f64>], outputs: &[&str]) -> Result<Table, Box<dyn Error>> {
+        let mut table: Option<Table> = None;
+
+        for case in cases {
+            for (k, v) in case {
+                self.edit(k, *v);
+            }
+
+            let (soln, _) = self.solve()?;
+
+            // warm-start the next case from this solution
+            for (k, v) in &soln {
+                if !self.edits.contains_key(k) {
+                    self.guesses.insert(k.to_string(), v.as_f64());
+                }
+            }
+
+            let t = table.get_or_insert_with(|| {
+                let mut inputs: Vec<String> = case.keys().cloned().collect();
+                inputs.sort();
+
+                let mut rest: Vec<String> = if outputs.is_empty() {
+                    soln.keys().filter(|&i| !case.contains_key(i)).cloned().collect()
+                } else {
+                    outputs.iter().map(|i| i.to_string()).collect()
+                };
+                if outputs.is_empty() {
+                    rest.sort();
+                }
+
+                inputs.extend(rest);
+                Table::new(inputs)
+            });
+
+            let row = t.columns.iter().map(
+                |i| match soln.get(i) {
+                    Some(v) => v.as_f64(),
+                    None => f64::NAN
+                }
+            ).collect();
+
+            t.rows.push(row);
+        }
+
+        Ok(table.unwrap_or_else(|| Table::new(vec![])))
+    }
+
+    /// Traces the branch of solutions as `parameter` varies over `range` via pseudo-arc-length
+    /// continuation (see `algos::arc_length`), returning a table of the parameter and the 
+    /// requested `outputs` with turning points and bifurcations flagged. If no outputs are 
+    /// given, every unknown in the system is reported.
+    /// 
+    /// `parameter` must be the only variable that the equations leave free. The system is 
+    /// first solved with `parameter` set to `range[0]`, and then every remaining equation is 
+    /// traced together. The edits given before the trace are kept as they are.
+    pub fn continuation(&mut self, parameter: &str, range: [f64; 2], step: f64, outputs: &[&str]) -> Result<Branch, Box<dyn Error>> {
+        let edits = self.edits.clone();
+
+        self.edit(parameter, range[0]);
+        let start = self.solve();
+
+        self.edits = edits;
+        let (start, _) = start?;
+
+        // the equations with every edit (other than the parameter) substituted in
+        let system: Vec<Expr> = self.equations.iter()
+            .map(|i| i.expr().substitute(&|v| self.edits.get(v).map(|j| j.as_f64())))
+            .filter(|i| !i.vars().is_empty())
+            .collect();
+
+        let mut guess = HashMap::new();
+        for i in &system {
+            for v in i.vars() {
+                if let Some((k, val)) = start.get_key_value(&v) {
+                    guess.insert(k.as_str(), val.clone());
+                }
+            }
+        }
+
+        let mut branch = arc_length(&system, guess, parameter, range, step, &self.config.settings)?;
+
+        if !outputs.is_empty() {
+            let mut columns = vec![parameter.to_string()];
+            columns.extend(outputs.iter().map(|i| i.to_string()));
+
+            let values: Vec<Vec<f64>> = columns.iter()
+                .map(|i| match (branch.table.column(i), self.edits.get(i)) {
+                    (Some(col), _) => col,
+                    (None, Some(v)) => vec![v.as_f64(); branch.table.rows.len()],
+                    (None, None) => vec![f64::NAN; branch.table.rows.len()]
+                })
+                .collect();
+
+            let mut table = Table::new(columns);
+            table.rows = (0..branch.table.rows.len())
+                .map(|r| values.iter().map(|c| c[r]).collect())
+                .collect();
+            branch.table = table;
+        }
+
+        Ok(branch)
+    }
 }
\ No newline at end of file
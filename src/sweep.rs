@@ -0,0 +1,134 @@
+use std::{collections::HashMap, error::Error};
+use crate::errors::SweepFormatError;
+
+/// The largest number of cases that the sweeps of a system may make.
+pub const MAX_CASES: usize = 100_000;
+
+/// Shorthand for a swept variable: the variable's name and 
+/// the values that it should take on.
+pub type Sweep = (String, Vec<f64>);
+
+/// A table of results produced by a parametric sweep. Each row holds the
+/// values of `columns` for one case of the sweep.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<f64>>
+}
+impl Table {
+    /// Initializes an empty table with the given column names.
+    pub fn new(columns: Vec<String>) -> Table {
+        Table { columns, rows: vec![] }
+    }
+
+    /// Returns the values in the given column, or `None` if the column does not exist.
+    /// # Example
+    /// ```
+    /// use nexsys::sweep::Table;
+    ///
+    /// let mut my_table = Table::new(vec!["x".to_string(), "y".to_string()]);
+    /// my_table.rows.push(vec![1.0, 2.0]);
+    /// my_table.rows.push(vec![3.0, 4.0]);
+    ///
+    /// assert_eq!(my_table.column("y").unwrap(), vec![2.0, 4.0]);
+    /// ```
+    pub fn column(&self, name: &str) -> Option<Vec<f64>> {
+        let idx = self.columns.iter().position(|i| i == name)?;
+        Some(self.rows.iter().map(|r| r[idx]).collect())
+    }
+
+    /// Returns the table formatted as comma-separated values.
+    pub fn to_csv(&self) -> String {
+        let mut res = self.columns.join(",") + "\n";
+        for r in &self.rows {
+            res += &r.iter().map(|i| i.to_string()).collect::<Vec<String>>().join(",");
+            res += "\n";
+        }
+        res
+    }
+
+    /// Returns the table formatted as a markdown table.
+    pub fn to_markdown(&self) -> String {
+        let mut res = format!("| {} |\n", self.columns.join(" | "));
+        res += &format!("|{}\n", "---|".repeat(self.columns.len()));
+        for r in &self.rows {
+            res += &format!("| {} |\n", r.iter().map(|i| i.to_string()).collect::<Vec<String>>().join(" | "));
+        }
+        res
+    }
+}
+
+/// Expands a set of swept variables into every combination of their values.
+/// # Example
+/// ```
+/// use nexsys::sweep::grid;
+///
+/// let my_sweeps = vec![
+///     ("a".to_string(), vec![1.0, 2.0]),
+///     ("b".to_string(), vec![3.0, 4.0, 5.0])
+/// ];
+///
+/// let cases = grid(&my_sweeps);
+///
+/// assert_eq!(cases.len(), 6);
+/// assert_eq!(cases[1]["a"], 1.0);
+/// assert_eq!(cases[1]["b"], 4.0);
+/// ```
+pub fn grid(sweeps: &[Sweep]) -> Vec<HashMap<String, f64>> {
+    let mut cases = vec![HashMap::new()];
+
+    for (var, vals) in sweeps {
+        let mut next = vec![];
+        for case in &cases {
+            for v in vals {
+                let mut c = case.clone();
+                c.insert(var.to_string(), *v);
+                next.push(c);
+            }
+        }
+        cases = next;
+    }
+
+    if sweeps.is_empty() {
+        return vec![]
+    }
+    cases
+}
+
+/// Reads sweep cases from comma-separated values. The first row names the
+/// swept variables and each following row is one case.
+/// # Example
+/// ```
+/// use nexsys::sweep::cases_from_csv;
+///
+/// let my_csv = "a, b\n1, 2\n3, 4\n";
+///
+/// let cases = cases_from_csv(my_csv).unwrap();
+///
+/// assert_eq!(cases.len(), 2);
+/// assert_eq!(cases[1]["b"], 4.0);
+/// ```
+pub fn cases_from_csv(text: &str) -> Result<Vec<HashMap<String, f64>>, Box<dyn Error>> {
+    let mut lines = text.lines().filter(|i| !i.trim().is_empty());
+
+    let header: Vec<String> = match lines.next() {
+        Some(h) => h.split(',').map(|i| i.trim().to_string()).collect(),
+        None => return Err(Box::new(SweepFormatError))
+    };
+
+    let mut cases = vec![];
+    for l in lines {
+        let vals = l.split(',')
+            .map(|i| i.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()?;
+
+        if vals.len() != header.len() {
+            return Err(Box::new(SweepFormatError))
+        }
+
+        cases.push(header.iter().cloned().zip(vals).collect());
+    }
+    Ok(cases)
+}
@@ -23,7 +23,7 @@ fn generate_num_denom_units(mut data:HashMap<String, HashMap<String, f64>>, qty:
     }
 
     if let Some(d) = data.get_mut(qty) {
-        d.extend(temp.into_iter());
+        d.extend(temp);
     }
 
     data
@@ -49,7 +49,7 @@ fn generate_fact_fact_units(mut data:HashMap<String, HashMap<String, f64>>, qty:
     }
 
     if let Some(d) = data.get_mut(qty) {
-        d.extend(temp.into_iter());
+        d.extend(temp);
     }
 
     data
@@ -65,7 +65,7 @@ fn generate_volume_units(mut data:HashMap<String, HashMap<String, f64>>) -> Hash
     }
 
     if let Some(d) = data.get_mut("VOLUME") {
-        d.extend(temp.into_iter());
+        d.extend(temp);
     }
     
     data
@@ -2,7 +2,9 @@ mod tools;
 
 use std::collections::HashMap;
 use nexsys::mvcalc::*;
+use nexsys::expr::Expr;
 use nexsys::algos::{Variable, stitch_hm};
+use nexsys::errors::SingularMatrixError;
 use tools::round;
 
 #[test]
@@ -102,8 +104,8 @@ fn test_nxn_row_get() {
 #[test]
 fn test_jacobian() {
     let my_sys = vec![
-        "x^2 + y",
-        "y   - x"
+        Expr::parse("x^2 + y").unwrap(),
+        Expr::parse("y   - x").unwrap()
     ];
 
     let guess = HashMap::from([
@@ -119,4 +121,280 @@ fn test_jacobian() {
     assert_eq!(cols["x"][1].round(), -1.0);
     assert_eq!(cols["y"][1].round(),  1.0);
     assert_eq!(cols["y"][0].round(),  1.0);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_analytic_jacobian() {
+    let my_sys = vec![
+        Expr::parse("P/1e5 - exp(L)").unwrap(),
+        Expr::parse("P*L^3 - sin(L)").unwrap()
+    ];
+
+    let guess = HashMap::from([
+        ("P", Variable::new(101325.0, None)),
+        ("L", Variable::new(0.5, None))
+    ]);
+
+    let my_j = jacobian(&my_sys, &guess).unwrap();
+    let exact = stitch_hm(my_j.vars.clone().unwrap(), my_j.to_vec());
+
+    let my_j = numeric_jacobian(&my_sys, &guess).unwrap();
+    let approx = stitch_hm(my_j.vars.clone().unwrap(), my_j.to_vec());
+
+    assert_eq!(exact["P"], vec![1e-5, 0.125]);
+    assert_eq!(exact["L"], vec![-0.5_f64.exp(), 3.0 * 101325.0 * 0.25 - 0.5_f64.cos()]);
+
+    for v in ["P", "L"] {
+        for i in 0..2 {
+            assert!((approx[v][i] - exact[v][i]).abs() <= 1e-3 * exact[v][i].abs());
+        }
+    }
+}
+
+#[test]
+fn test_dual_functions() {
+    let x = Dual::var(0.5);
+    let y = Dual::from(2.0);
+
+    assert_eq!(x.powf(y).du, 1.0);
+    assert_eq!(y.powf(x).du, 2_f64.sqrt() * 2_f64.ln());
+    assert_eq!((x * x).sqrt().du, 1.0);
+    assert_eq!(x.ln().du, 2.0);
+    assert_eq!(x.atan2(y).du, 2.0 / 4.25);
+    assert_eq!((y % x).du, -4.0);
+    assert_eq!(x.step(f64::floor).du, 0.0);
+}
+
+#[test]
+fn test_lu_solve() {
+    let cols = vec![
+        vec![ 2.0,  1.0, -1.0,  0.0,  3.0],
+        vec![ 1.0, -3.0,  2.0,  1.0,  0.0],
+        vec![ 0.0,  2.0,  4.0, -2.0,  1.0],
+        vec![ 5.0,  0.0,  1.0,  1.0, -1.0],
+        vec![-1.0,  1.0,  0.0,  3.0,  2.0]
+    ];
+    let x = vec![1.0, -2.0, 0.5, 3.0, -1.0];
+
+    // b = Ax
+    let b: Vec<f64> = (0..5).map(|r| (0..5).map(|c| cols[c][r] * x[c]).sum()).collect();
+
+    let my_matrix = NxN::from_cols(cols, None).unwrap();
+    let lu = my_matrix.lu().unwrap();
+
+    for (i, j) in lu.solve(&b).unwrap().iter().zip(&x) {
+        assert_thou!(*i, *j);
+    }
+
+    // Aᵀy = c is consistent with Ax = b, since yᵀb = yᵀAx = cᵀx
+    let c = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+    let y = lu.solve_transpose(&c).unwrap();
+    let yb: f64 = y.iter().zip(&b).map(|(i, j)| i * j).sum();
+    let cx: f64 = c.iter().zip(&x).map(|(i, j)| i * j).sum();
+    assert_thou!(yb, cx);
+
+    assert!(lu.solve(&[1.0, 2.0]).is_err());
+}
+
+#[test]
+fn test_lu_det_and_condition() {
+    let my_matrix = NxN::from_cols(vec![
+        vec![4.0, 2.0],
+        vec![3.0, 1.0]
+    ], None).unwrap();
+
+    assert_thou!(my_matrix.det(), -2.0);
+    assert_thou!(my_matrix.condition(), 21.0); // |A| = 6, |A^-1| = 3.5
+
+    assert_eq!(NxN::identity(4).det(), 1.0);
+    assert_eq!(NxN::identity(4).condition(), 1.0);
+
+    let my_matrix = NxN::from_cols(vec![
+        vec![1.0, 0.0],
+        vec![0.0, 1e-8]
+    ], None).unwrap();
+
+    assert_thou!(my_matrix.condition() / 1e8, 1.0);
+}
+
+#[test]
+fn test_lu_singular() {
+    let my_matrix = NxN::from_cols(vec![
+        vec![1.0, 0.0, 1.0],
+        vec![0.0, 1.0, 0.0],
+        vec![2.0, 0.0, 2.0]
+    ], Some(vec!["a", "b", "c"])).unwrap();
+
+    let err = my_matrix.lu().unwrap_err();
+    let err = err.downcast_ref::<SingularMatrixError>().unwrap();
+
+    assert_eq!(err.vars, vec!["c"]);
+    assert_eq!(my_matrix.det(), 0.0);
+    assert_eq!(my_matrix.condition(), f64::INFINITY);
+}
+
+#[test]
+fn test_sparse_lu() {
+    // a matrix that needs pivoting: zeros on the diagonal and couplings to distant columns
+    let n = 60;
+    let mut my_matrix = Sparse::new(n, None);
+    for i in 0..n {
+        my_matrix.set(i, (i + 1) % n, 4.0 + i as f64 / 10.0);
+        my_matrix.set(i, (i + 7) % n, -1.5);
+        my_matrix.set(i, (i * 13) % n, 0.5);
+    }
+    let x: Vec<f64> = (0..n).map(|i| (i as f64).sin()).collect();
+    let b = my_matrix.mul_vec(&x).unwrap();
+
+    let sparse = my_matrix.solve(&b).unwrap();
+    let dense = my_matrix.to_dense().unwrap().solve(&b).unwrap();
+
+    for i in 0..n {
+        assert_thou!(sparse[i], x[i]);
+        assert_thou!(dense[i], x[i]);
+    }
+}
+
+#[test]
+fn test_sparse_singular() {
+    let mut my_matrix = Sparse::new(3, Some(vec!["a".to_string(), "b".to_string(), "c".to_string()]));
+    my_matrix.set(0, 0, 1.0);
+    my_matrix.set(0, 2, 2.0);
+    my_matrix.set(1, 1, 1.0);
+    my_matrix.set(2, 0, 1.0);
+    my_matrix.set(2, 2, 2.0);
+
+    let err = my_matrix.lu().unwrap_err();
+    let err = err.downcast_ref::<SingularMatrixError>().unwrap();
+
+    assert_eq!(err.vars, vec!["c"]);
+}
+
+#[test]
+fn test_sparse_condition() {
+    let n = 30;
+    let mut my_matrix = Sparse::new(n, None);
+    let mut transpose = Sparse::new(n, None);
+    for i in 0..n {
+        for (j, v) in [(i, 0.5), ((i + 1) % n, 4.0 + i as f64 / 10.0), ((i + 7) % n, -1.5)] {
+            my_matrix.set(i, j, v);
+            transpose.set(j, i, v);
+        }
+    }
+    let x: Vec<f64> = (0..n).map(|i| (i as f64).cos()).collect();
+
+    let lu = my_matrix.lu().unwrap();
+    let solved = lu.solve_transpose(&transpose.mul_vec(&x).unwrap()).unwrap();
+    for i in 0..n {
+        assert_thou!(solved[i], x[i]);
+    }
+
+    let dense = my_matrix.to_dense().unwrap().condition();
+    assert!((lu.condition() / dense - 1.0).abs() < 1e-6);
+
+    // nearly singular matrices still factor, but are badly conditioned
+    let mut my_matrix = Sparse::new(2, None);
+    my_matrix.set(0, 0, 1.0);
+    my_matrix.set(0, 1, 2.0);
+    my_matrix.set(1, 0, 1.0);
+    my_matrix.set(1, 1, 2.0 + 1e-13);
+    assert!(my_matrix.lu().unwrap().condition() > 1e12);
+}
+
+#[test]
+fn test_sparse_jacobian() {
+    let my_sys = vec![
+        Expr::parse("x^2 + y").unwrap(),
+        Expr::parse("y*z - 2").unwrap(),
+        Expr::parse("exp(z)").unwrap()
+    ];
+
+    let guess = HashMap::from([
+        ("x", Variable::new(3.0, None)),
+        ("y", Variable::new(2.0, None)),
+        ("z", Variable::new(0.5, None))
+    ]);
+
+    let sparse = sparse_jacobian(&my_sys, &guess).unwrap();
+    let dense = jacobian(&my_sys, &guess).unwrap();
+
+    assert_eq!(sparse.nonzeros(), 5);
+    assert_eq!(sparse.vars, dense.vars);
+    assert_eq!(sparse.to_dense().unwrap(), dense);
+
+    let numeric = numeric_sparse_jacobian(&my_sys, &guess).unwrap();
+    assert_eq!(numeric.nonzeros(), 5);
+}
+#[test]
+fn test_svd() {
+    let my_matrix = NxN::from_cols(vec![
+        vec![2.0, 1.0, 0.0],
+        vec![1.0, 3.0, 1.0],
+        vec![0.0, 1.0, 4.0]
+    ], Some(vec!["x", "y", "z"])).unwrap();
+
+    // a full rank matrix is solved exactly
+    let svd = my_matrix.svd();
+    let x = svd.solve(&[3.0, 5.0, 5.0]).unwrap();
+
+    assert_eq!(svd.rank(), 3);
+    assert!(svd.null_space().is_empty());
+    for i in x {
+        assert_thou!(i, 1.0);
+    }
+
+    // the product of the singular values is |det|
+    let prod: f64 = svd.singular_values().iter().product();
+    assert_thou!(prod, my_matrix.det().abs());
+
+    // z is the sum of x and y, so the direction x + y - z is undetermined
+    let singular = NxN::from_cols(vec![
+        vec![1.0, 0.0, 2.0],
+        vec![0.0, 1.0, 1.0],
+        vec![1.0, 1.0, 3.0]
+    ], Some(vec!["x", "y", "z"])).unwrap();
+
+    let svd = singular.svd();
+    assert_eq!(svd.rank(), 2);
+    assert_eq!(svd.undetermined(), vec!["x", "y", "z"]);
+
+    let null = &svd.null_space()[0];
+    assert_thou!(null[0].abs(), 1.0 / 3.0_f64.sqrt());
+    assert_thou!(null[0], null[1]);
+    assert_thou!(null[0], -null[2]);
+
+    // an inconsistent system is solved in the least-squares sense
+    let b = [1.0, 1.0, 1.0];
+    let x = svd.solve(&b).unwrap();
+    let r: Vec<f64> = (0..3)
+        .map(|i| singular.get_row(i).iter().zip(&x).map(|(a, b)| a * b).sum::<f64>() - b[i])
+        .collect();
+
+    // the residual is orthogonal to every column (Aᵀr = 0)
+    for c in singular.to_vec() {
+        assert_thou!(c.iter().zip(&r).map(|(a, b)| a * b).sum::<f64>(), 0.0);
+    }
+}
+
+#[test]
+fn test_svd_condition() {
+    // too poorly conditioned for LU, though the singular values are within 1/MAX_CONDITION
+    let my_matrix = NxN::from_cols(vec![
+        vec![1.0, 1.0, 1.0],
+        vec![0.0, 4e-12, 0.0],
+        vec![0.0, 0.0, 4e-12]
+    ], Some(vec!["x", "y", "z"])).unwrap();
+
+    assert!(my_matrix.condition() >= MAX_CONDITION);
+    let s = my_matrix.svd().singular_values().to_vec();
+    assert!(s[2] > s[0] / MAX_CONDITION);
+
+    // so the SVD must not treat it as full rank, or its step would be the refused LU step
+    let svd = my_matrix.svd();
+    assert_eq!(svd.rank(), 1);
+    assert_eq!(svd.undetermined(), vec!["y", "z"]);
+
+    let x = svd.solve(&[1.0, 1.001, 1.0]).unwrap();
+    assert_thou!(x[0], 1.0);
+    assert!(x.iter().all(|i| i.abs() < 2.0));
+}
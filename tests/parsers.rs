@@ -1,4 +1,5 @@
-use nexsys::{parsing::{conditionals, conversions}, units::unit_data};
+use nexsys::{parsing::{compile, conditionals, conversions, convergence_criteria, options, root_choices, sweeps, typical_magnitudes}, units::unit_data, expr::Expr, algos::{Convergence, JacobianUpdate, Method, Norm, RootChoice}, config::SolverConfig};
+use nexsys::errors::SweepRangeError;
 
 #[test]
 fn test_conditional_parser() {
@@ -65,6 +66,69 @@ fn test_conversion_parser() {
     assert_eq!(formatted.as_str(), "2.54\n2.54\n0.0000630902")
 }
 
+#[test]
+fn test_sweep_parser() {
+    let my_code = "sweep D from 0.01 to 0.05 step 0.01\nsweep L in [1, 2.5]\nx = D * L";
+
+    let res = sweeps(my_code).unwrap();
+
+    assert_eq!(res.len(), 2);
+    assert_eq!(res[0].0, "D");
+    assert_eq!(res[0].1.len(), 5);
+    assert!((res[0].1[4] - 0.05).abs() < 1e-12);
+    assert_eq!(res[1], ("L".to_string(), vec![1.0, 2.5]));
+}
+
+#[test]
+fn test_sweep_limits() {
+    for bad in [
+        "sweep x from 0 to 1 step 0",
+        "sweep x from 0 to 1 step -0.1",
+        "sweep x from 1 to 0 step 0.1",
+        "sweep x from 0 to 1 step 1e-12",
+        "sweep x from 0 to 999 step 1\nsweep y from 0 to 999 step 1"
+    ] {
+        let err = sweeps(bad).unwrap_err();
+        assert!(err.downcast_ref::<SweepRangeError>().is_some(), "{bad}");
+    }
+
+    assert_eq!(sweeps("sweep x from 0 to 99 step 1\nsweep y from 0 to 99 step 1").unwrap().len(), 2);
+}
+
+#[test]
+fn test_expr_parser() {
+    let none = |_: &str| None;
+
+    assert_eq!(Expr::parse("-2^2").unwrap().eval(&none).unwrap(), -4.0);
+    assert_eq!(Expr::parse("2^3^2").unwrap().eval(&none).unwrap(), 512.0);
+    assert_eq!(Expr::parse("2 - 3 - 4").unwrap().eval(&none).unwrap(), -5.0);
+    assert_eq!(Expr::parse("1.5E-3 * 2e3 + .5").unwrap().eval(&none).unwrap(), 3.5);
+    assert_eq!(Expr::parse("max(1, 7, 3) % 4").unwrap().eval(&none).unwrap(), 3.0);
+
+    let my_expr = Expr::parse("(a - (b - c))^(-d) / (e*f)").unwrap();
+    assert_eq!(my_expr.to_string(), "(a - (b - c))^(-d)/(e*f)");
+    assert_eq!(Expr::parse(&my_expr.to_string()).unwrap(), my_expr);
+
+    assert!(Expr::parse("x +").is_err());
+    assert!(Expr::parse("foo(x)").is_err());
+    assert!(Expr::parse("atan2(x)").is_err());
+    assert!(Expr::parse("(x + 1").is_err());
+}
+
+#[test]
+fn test_expr_substitution() {
+    let my_expr = Expr::parse("x2 + max(x, e) + exp(x)").unwrap();
+
+    let subbed = my_expr.substitute(&|v| match v {
+        "x" => Some(0.0),
+        "e" => Some(3.0),
+        _ => None
+    });
+
+    assert_eq!(subbed.vars(), vec!["x2"]);
+    assert_eq!(subbed.eval(&|v| if v == "x2" { Some(1.0) } else { None }).unwrap(), 5.0);
+}
+
 #[test]
 fn test_unit_data() {
 
@@ -77,4 +141,119 @@ fn test_unit_data() {
     // println!("{}",_ud["VOLUMETRIC FLOW"]["m^3/s"]);
     // println!("{}",_ud["VOLUMETRIC FLOW"]["gpm"]);
     // println!("{}",_ud["VELOCITY"]["mph"]);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_root_parser() {
+    let my_code = r#"
+    root smallest for x
+    root nearest -2.5e-1 for y_1
+    root largest for z
+    x^2 = 4
+    "#;
+
+    let roots = root_choices(my_code).unwrap();
+
+    assert_eq!(roots.len(), 3);
+    assert_eq!(roots["x"], RootChoice::Smallest);
+    assert_eq!(roots["y_1"], RootChoice::Nearest(-0.25));
+    assert_eq!(roots["z"], RootChoice::Largest);
+
+    assert!(root_choices("root nearest abc for x").is_err());
+}
+
+#[test]
+fn test_typical_parser() {
+    let my_code = r#"
+    typical 1e5 for p
+    typical 2.5E-3 for d_1
+    p * d_1 = 250
+    "#;
+
+    let typical = typical_magnitudes(my_code).unwrap();
+
+    assert_eq!(typical.len(), 2);
+    assert_eq!(typical["p"], 1e5);
+    assert_eq!(typical["d_1"], 2.5e-3);
+
+    assert!(typical_magnitudes("typical big for x").is_err());
+}
+#[test]
+fn test_convergence_parser() {
+    let my_code = r#"
+    norm inf
+    tolerance relative 1e-8
+    tolerance step 1e-12
+    tolerance step 0.5 for x
+    tolerance 1e-3 for equation 2
+    x + y = 3
+    x - y = 1
+    "#;
+
+    let criteria = convergence_criteria(my_code, Convergence::default()).unwrap();
+
+    assert_eq!(criteria.norm, Norm::Infinity);
+    assert_eq!(criteria.relative, Some(1e-8));
+    assert_eq!(criteria.step, Some(1e-12));
+    assert_eq!(criteria.variables["x"], 0.5);
+    assert_eq!(criteria.equations, vec![None, Some(1e-3)]);
+
+    // equations are counted as written, but stored in the order of the compiled system
+    let my_code = r#"
+    tolerance 1e-3 for equation 4
+    tolerance 1e-4 for equation 2
+    tolerance 1e-5 for equation 3
+    x + y = 3
+    if x >= 1:
+        y = 2
+    else:
+        y = 1
+    end
+    x - y = 1
+    "#;
+
+    let criteria = convergence_criteria(my_code, Convergence::default()).unwrap();
+    assert_eq!(compile(my_code).unwrap().lines().filter(|i| i.contains('=')).count(), 3);
+    assert_eq!(criteria.equations, vec![None, Some(1e-5), Some(1e-3)]);
+
+    assert!(convergence_criteria(&format!("{my_code}\ntolerance 1e-3 for equation 5"), Convergence::default()).is_err());
+    assert!(convergence_criteria("tolerance 1e-3 for equation 0", Convergence::default()).is_err());
+    assert!(convergence_criteria("tolerance relative small", Convergence::default()).is_err());
+}
+
+#[test]
+fn test_options_parser() {
+    let my_code = r#"
+    options {
+        tolerance: 1e-8
+        update: good_broyden
+        fallbacks: [homotopy, levenberg_marquardt]
+        convergence.norm: infinity
+        typical.p: 1e5
+        multistart.starts: 5
+        "options can have comments too"
+    }
+    p * a = 3.5e9
+    "#;
+
+    let config = options(my_code, &SolverConfig::default()).unwrap();
+
+    assert_eq!(config.settings.tolerance, 1e-8);
+    assert_eq!(config.settings.max_iterations, 300); // left as it was
+    assert_eq!(config.settings.update, JacobianUpdate::GoodBroyden);
+    assert_eq!(config.fallbacks, vec![Method::Homotopy, Method::LevenbergMarquardt]);
+    assert_eq!(config.settings.convergence.norm, Norm::Infinity);
+    assert_eq!(config.settings.typical["p"], 1e5);
+    assert_eq!(config.settings.multistart.unwrap().starts, 5);
+
+    // the block isn't part of the system
+    assert_eq!(compile(my_code).unwrap().trim(), "p * a = 3.5e9");
+
+    // later blocks override earlier ones (`nxc` adds its command line options this way)
+    let config = options(&format!("{my_code}\noptions {{\n    tolerance: 1e-6\n}}\n"), &SolverConfig::default()).unwrap();
+    assert_eq!(config.settings.tolerance, 1e-6);
+    assert_eq!(config.settings.update, JacobianUpdate::GoodBroyden);
+
+    assert!(options("options {\n    speed: 11\n}", &SolverConfig::default()).is_err());
+    assert!(options("options {\n    tolerance: small\n}", &SolverConfig::default()).is_err());
+}
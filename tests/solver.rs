@@ -1,14 +1,18 @@
 mod tools;
 
-use std::collections::HashMap;
-use nexsys::algos::{BlockMgr, Equation, Variable};
+use std::{collections::HashMap, sync::{Arc, Mutex}, time::Duration};
+use nexsys::algos::{all_roots, arc_length, brent, levenberg_marquardt, multistart, mv_newton_raphson, newton_raphson, BlockMgr, Convergence, Derivative, Equation, Incidence, JacobianUpdate, Method, Multistart, Norm, Point, Sampling, Settings, Variable};
+use nexsys::config::SolverConfig;
+use nexsys::expr::Expr;
 use nexsys::solver::Nexsys;
-use nexsys::solve;
+use nexsys::{solve, sweep};
+use nexsys::errors::{DomainRequiredError, NoSignChangeError, SolverCancelledError, SolverConstraintError, SolverConvergenceError, SolverTimeoutError, TimeLimitError};
+use nexsys::monitor::{CancellationToken, Observer};
 
 #[test]
 fn test_equation() {
     let my_eqn = "x = y + z + 2";
-    let eqn = Equation::new(my_eqn);
+    let eqn = Equation::new(my_eqn).unwrap();
 
     let vars = vec!["x".to_string(), "y".to_string(), "z".to_string()];
     let uks = vec!["y".to_string(), "z".to_string()];
@@ -27,14 +31,14 @@ fn test_block_mgr() {
     let ctx = HashMap::from([("a".to_string(), Variable::new(0.0, None))]);
     let mut bkm = BlockMgr::new(&ctx);
 
-    let my_eqns = vec![
+    let my_eqns = [
         "2*x + 5*y + 2*z = -38 + a",
         "3*x - 2*y + 4*z = 17",
         "-6*x + y - 7*z = -12"
     ];
 
     for i in my_eqns.iter().map(
-        |e| Equation::new(e)
+        |e| Equation::new(e).unwrap()
     ) {
         bkm.add_item(&i);
     }
@@ -47,7 +51,7 @@ fn test_block_mgr() {
 #[should_panic]
 #[test]
 fn test_block_mgr_guard_clause() {
-    let my_eqn = Equation::new("x^2");
+    let my_eqn = Equation::new("x^2").unwrap();
 
     let ctx = HashMap::new();
 
@@ -58,15 +62,59 @@ fn test_block_mgr_guard_clause() {
     bkm.constrained().unwrap();
 }
 
+#[test]
+fn test_incidence() {
+    let ctx = HashMap::new();
+    let mut graph = Incidence::new(&ctx);
+
+    let my_eqns = [
+        "w = x * z",
+        "x + y + z = 6",
+        "x - y = -1",
+        "x + z = 4"
+    ];
+
+    for i in my_eqns.iter().map(
+        |e| Equation::new(e).unwrap()
+    ) {
+        graph.add_item(&i);
+    }
+
+    let blocks = graph.constrained().unwrap();
+
+    assert_eq!(blocks.len(), 2);
+
+    let mut first = blocks[0].vars.clone();
+    first.sort();
+
+    assert_eq!(first, vec!["x", "y", "z"]);
+    assert_eq!(blocks[1].vars, vec!["w"]);
+
+    // each block knows where its equations are in the system
+    assert_eq!(blocks[0].rows, vec![1, 2, 3]);
+    assert_eq!(blocks[1].rows, vec![0]);
+}
+
+#[test]
+fn test_incidence_under_constrained() {
+    let ctx = HashMap::new();
+    let mut graph = Incidence::new(&ctx);
+
+    graph.add_item(&Equation::new("x + y = 2").unwrap());
+    graph.add_item(&Equation::new("z = x * 2").unwrap());
+
+    assert!(graph.constrained().is_none());
+}
+
 #[test]
 fn test_solver_engine() {
-    let my_sys = Nexsys::new(r#"
+    let mut my_sys = Nexsys::new(r#"
         a = 4
         b = a + 5
         x + y = b
         x - y = a"#, 
         
-        1e-10, 300, false );
+        SolverConfig::default() ).unwrap();
 
     let soln = match my_sys.solve() {
         Ok(o) => o,
@@ -74,7 +122,9 @@ fn test_solver_engine() {
     };
 
 
-    println!("{}", soln.1.join("\n"));
+    for step in &soln.1 {
+        println!("{step}");
+    }
 
     let x = "x".to_string();
     let y = "y".to_string();
@@ -94,7 +144,7 @@ fn test_solver_w_conditional() {
     }
     "#;
 
-    let (soln, _) = solve(my_code, None, None, false).unwrap();
+    let (soln, _) = solve(my_code, &SolverConfig::default()).unwrap();
 
     assert_thou!(soln["b"].as_f64(), 2.0);
 }
@@ -107,7 +157,926 @@ fn test_solver_w_conversions() {
     c = b * [ft->cm]
     "#;
 
-    let (soln, _) = solve(my_code, Some(1E-10), None, false).unwrap();
+    let (soln, _) = solve(my_code, &SolverConfig::builder().tolerance(1E-10).build()).unwrap();
 
     assert_thou!(soln["c"].as_f64(), 30.48);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_solver_partial_overlap() {
+    let my_code = r#"
+    x + y + z = 6
+    x - y = -1
+    x + z = 4
+    w = x * z
+    "#;
+
+    let (soln, _) = solve(my_code, &SolverConfig::default()).unwrap();
+
+    assert_thou!(soln["x"].as_f64(), 1.0);
+    assert_thou!(soln["y"].as_f64(), 2.0);
+    assert_thou!(soln["z"].as_f64(), 3.0);
+    assert_thou!(soln["w"].as_f64(), 3.0);
+}
+
+#[test]
+fn test_solver_constraint_diagnosis() {
+    let my_code = r#"
+    a = 2
+    x + y = a
+    x - y = 0
+    x * y = 1
+    z + w = x
+    "#;
+
+    let err = solve(my_code, &SolverConfig::default()).unwrap_err();
+    let diag = err.downcast_ref::<SolverConstraintError>().unwrap();
+
+    assert_eq!(diag.degrees_of_freedom, 1);
+    assert_eq!(diag.unused, vec!["z + w = x"]);
+    assert_eq!(diag.suggestions.len(), 1);
+    assert!(diag.overdetermined.is_empty());
+    assert_thou!(diag.solution["x"].as_f64(), 1.0);
+}
+
+#[test]
+fn test_incidence_over_constrained() {
+    let ctx = HashMap::new();
+    let mut graph = Incidence::new(&ctx);
+
+    graph.add_item(&Equation::new("x + y = 2").unwrap());
+    graph.add_item(&Equation::new("x - y = 0").unwrap());
+    graph.add_item(&Equation::new("x * y = 1").unwrap());
+    graph.add_item(&Equation::new("z + w = 1").unwrap());
+
+    let diag = graph.diagnose();
+
+    assert_eq!(diag.degrees_of_freedom, 0);
+    assert_eq!(diag.overdetermined.len(), 1);
+    assert_eq!(diag.overdetermined[0].len(), 3);
+    assert_eq!(diag.suggestions.len(), 1);
+}
+
+#[test]
+fn test_solver_log() {
+    let my_code = r#"
+    a = 4
+    x + y = a
+    x - y = 2
+    "#;
+
+    let (_, log) = solve(my_code, &SolverConfig::default()).unwrap();
+
+    assert_eq!(log.len(), 2);
+
+    assert_eq!(log[0].targets, vec!["a"]);
+    assert_eq!(log[0].method, Method::Direct);
+    assert!(log[0].converged);
+
+    let mut targets = log[1].targets.clone();
+    targets.sort();
+
+    assert_eq!(targets, vec!["x", "y"]);
+    assert_eq!(log[1].equations, vec!["x + y = a", "x - y = 2"]);
+    assert_eq!(log[1].method, Method::MvNewton);
+    assert!(log[1].residual < 1e-10);
+}
+
+#[test]
+fn test_solver_reuse() {
+    let mut my_sys = Nexsys::new(r#"
+        b = a + 5
+        x + y = b
+        x - y = a"#, 
+        
+        SolverConfig::default() ).unwrap();
+
+    my_sys.edit("a", 4.0);
+    let (first, _) = my_sys.solve().unwrap();
+
+    assert_thou!(first["x"].as_f64(), 6.5);
+
+    my_sys.edit("a", 2.0);
+    let (second, log) = my_sys.solve().unwrap();
+
+    assert_thou!(second["x"].as_f64(), 4.5);
+    assert_thou!(second["y"].as_f64(), 2.5);
+    assert_eq!(log.len(), 2);
+}
+
+#[test]
+fn test_solver_sweep() {
+    let my_code = r#"
+    sweep D from 1 to 4 step 1
+    x^2 = D
+    y = 2 * x
+    guess 1 for x
+    "#;
+
+    let table = sweep(my_code, &["y"], &SolverConfig::default()).unwrap();
+
+    assert_eq!(table.columns, vec!["D", "y"]);
+    assert_eq!(table.rows.len(), 4);
+    assert_thou!(table.rows[3][1], 4.0);
+    assert_thou!(table.column("y").unwrap()[0], 2.0);
+}
+
+#[test]
+fn test_solver_variable_names() {
+    let mut my_sys = Nexsys::new(r#"
+        x = 2
+        x2 = x + 1
+        y = max(x, x2) + exp(x - 2)
+        z = e * x"#, 
+        
+        SolverConfig::default() ).unwrap();
+
+    my_sys.edit("e", 3.0);
+
+    let (soln, _) = my_sys.solve().unwrap();
+
+    assert_thou!(soln["x2"].as_f64(), 3.0);
+    assert_thou!(soln["y"].as_f64(), 4.0);
+    assert_thou!(soln["z"].as_f64(), 6.0);
+}
+
+#[test]
+fn test_solver_direct() {
+    let my_code = r#"
+    a = 2
+    b = 3*a + 2
+    (c - b)/4 = a*c
+    d^2 = b
+    "#;
+
+    let (soln, log) = solve(my_code, &SolverConfig::default()).unwrap();
+
+    assert_eq!(soln["a"].as_f64(), 2.0);
+    assert_eq!(soln["b"].as_f64(), 8.0);
+    assert_eq!(soln["c"].as_f64(), -8.0 / 7.0);
+    assert_thou!(soln["d"].as_f64(), 8_f64.sqrt());
+
+    for step in &log {
+        match step.targets[0].as_str() {
+            "d" => assert_ne!(step.method, Method::Direct),
+            _ => assert_eq!(step.method, Method::Direct)
+        }
+    }
+}
+
+#[test]
+fn test_solver_derivatives() {
+    let my_code = r#"
+        P * V = n * 8.314 * T
+        P = 101325 + 1000 * exp(V)
+        T = 300
+        n = 2
+    "#;
+
+    let mut my_sys = Nexsys::new(my_code, SolverConfig::builder().tolerance(1e-8).max_iterations(300).build()).unwrap();
+    my_sys.guess("P", 1e5);
+
+    let (analytic, _) = my_sys.solve().unwrap();
+
+    my_sys.configure(Settings { derivative: Derivative::FiniteDifference, ..Settings::new(1e-8, 300) });
+    let (numeric, _) = my_sys.solve().unwrap();
+
+    assert_eq!(my_sys.settings().derivative, Derivative::FiniteDifference);
+    assert_thou!(analytic["V"].as_f64(), numeric["V"].as_f64());
+    assert_thou!(analytic["P"].as_f64(), numeric["P"].as_f64());
+}
+
+#[test]
+fn test_solver_line_search() {
+    let my_code = r#"
+        P * V = n * 8.314 * T
+        P = 101325 + 1000 * exp(V)
+        T = 300
+        n = 2
+        atan(x) = 0
+    "#;
+
+    let mut my_sys = Nexsys::new(my_code, SolverConfig::builder().tolerance(1e-8).max_iterations(300).build()).unwrap();
+    my_sys.guess("x", 3.0);
+
+    let (soln, _) = my_sys.solve().unwrap();
+
+    assert_thou!(soln["V"].as_f64(), 0.0487);
+    assert_thou!(soln["x"].as_f64(), 0.0);
+
+    // full newton steps diverge on both problems, so the fallback methods are needed
+    my_sys.configure(Settings { line_search: false, ..Settings::new(1e-8, 300) });
+    let (_, log) = my_sys.solve().unwrap();
+
+    assert!(log.iter().any(|i| i.method == Method::Brent));
+    assert!(log.iter().any(|i| i.method == Method::Homotopy));
+
+    // ...unless the steps are capped
+    my_sys.max_step("x", 1.0);
+    my_sys.max_step("V", 0.1);
+    my_sys.max_step("P", 1e4);
+    my_sys.guess("P", 1e5);
+
+    let (soln, log) = my_sys.solve().unwrap();
+
+    assert!(log.iter().all(|i| i.converged));
+    assert_thou!(soln["V"].as_f64(), 0.0487);
+    assert_thou!(soln["x"].as_f64(), 0.0);
+}
+
+#[test]
+fn test_solver_singular_jacobian() {
+    let my_code = r#"
+        x^2 + y^2 - x = 3
+        x*y + 0.5*y^2 = 2
+    "#;
+
+    // the jacobian is singular at the default guess, so the first newton step uses its pseudo-inverse
+    let (soln, log) = solve(my_code, &SolverConfig::default()).unwrap();
+    let step = log.last().unwrap();
+
+    assert_eq!(step.method, Method::MvNewton);
+    assert!(step.converged);
+
+    let mut undetermined = step.undetermined.clone();
+    undetermined.sort();
+    assert_eq!(undetermined, vec!["x", "y"]);
+    assert!(step.to_string().contains("rank-deficient jacobian"));
+
+    let (x, y) = (soln["x"].as_f64(), soln["y"].as_f64());
+    assert_thou!(x*x + y*y - x, 3.0);
+    assert_thou!(x*y + 0.5*y*y, 2.0);
+
+    // levenberg-marquardt handles the singular jacobian by damping instead
+    let my_sys = vec![
+        Expr::parse("x^2 + y^2 - x - 3").unwrap(),
+        Expr::parse("x*y + 0.5*y^2 - 2").unwrap()
+    ];
+    let guess = HashMap::from([
+        ("x", Variable::new(1.0, None)),
+        ("y", Variable::new(1.0, None))
+    ]);
+    let ans = levenberg_marquardt(&my_sys, guess, &Settings::default()).unwrap();
+
+    assert!(ans.converged());
+    assert!(ans.undetermined.is_empty());
+}
+
+#[test]
+fn test_solver_sparse_singular_jacobian() {
+    let my_code = r#"
+        x^2 + y^2 - x = 3
+        x*y + 0.5*y^2 = 2
+    "#;
+
+    // the jacobian is nearly (but not exactly) singular at the guess, so its sparse LU
+    // succeeds but is too poorly conditioned to use
+    for threshold in [1, 50] {
+        let mut my_sys = Nexsys::new(my_code, SolverConfig::builder().sparse_threshold(threshold).build()).unwrap();
+        my_sys.guess("y", 1.0 + 1e-14);
+
+        let (soln, log) = my_sys.solve().unwrap();
+        let step = log.last().unwrap();
+        assert!(step.converged);
+
+        let mut undetermined = step.undetermined.clone();
+        undetermined.sort();
+        assert_eq!(undetermined, vec!["x", "y"], "sparse_threshold {threshold}");
+
+        let (x, y) = (soln["x"].as_f64(), soln["y"].as_f64());
+        assert_thou!(x*x + y*y - x, 3.0);
+        assert_thou!(x*y + 0.5*y*y, 2.0);
+    }
+}
+
+#[test]
+fn test_brent() {
+    let settings = Settings::default();
+    let my_eqn = Expr::parse("(x - 1)*(x - 4)*(x - 9)").unwrap();
+
+    // the root nearest to the guess is chosen when the domain has several
+    let root = brent(&my_eqn, ("x", Variable::new(5.0, Some([0.0, 10.0]))), &settings).unwrap();
+    assert!(root.converged());
+    assert_thou!(root.unwrap().1.as_f64(), 4.0);
+
+    // without a domain, the search expands outward from the guess
+    let root = brent(&my_eqn, ("x", Variable::new(-50.0, None)), &settings).unwrap();
+    assert!(root.converged());
+    assert_thou!(root.unwrap().1.as_f64(), 1.0);
+
+    // sign changes at discontinuities are not roots
+    let my_eqn = Expr::parse("1/x").unwrap();
+    let root = brent(&my_eqn, ("x", Variable::new(1.0, Some([-1.0, 2.0]))), &settings).unwrap();
+    assert!(!root.converged());
+
+    // no sign change means no root
+    let my_eqn = Expr::parse("x^2 + 1").unwrap();
+    let err = brent(&my_eqn, ("x", Variable::new(1.0, None)), &settings).err().unwrap();
+    assert!(err.downcast_ref::<NoSignChangeError>().is_some());
+}
+
+#[test]
+fn test_solver_brent_fallback() {
+    // |x^2 + 1| has a minimum at x = 0, but no root
+    assert!(solve("x^2 + 1 = 0", &SolverConfig::default()).is_err());
+
+    // newton's method can't leave a point where the derivative is zero
+    let (soln, log) = solve("guess 3 for x\n (x - 3)^3 = 1", &SolverConfig::default()).unwrap();
+
+    assert_eq!(log.last().unwrap().method, Method::Brent);
+    assert_thou!(soln["x"].as_f64(), 4.0);
+}
+
+#[test]
+fn test_all_roots() {
+    let settings = Settings::default();
+
+    // a root on the edge of a scanned interval is only reported once
+    let my_eqn = Expr::parse("sin(x)").unwrap();
+    let roots = all_roots(&my_eqn, ("x", Variable::new(0.0, Some([-5.0, 10.0]))), &settings).unwrap();
+
+    assert!(roots.converged());
+    let roots = roots.unwrap();
+    assert_eq!(roots.len(), 5);
+    for (i, r) in roots.iter().enumerate() {
+        assert_thou!(*r, (i as f64 - 1.0) * std::f64::consts::PI);
+    }
+
+    // tangent roots and discontinuities are not found
+    let my_eqn = Expr::parse("(x - 1)^2 * tan(x)").unwrap();
+    let roots = all_roots(&my_eqn, ("x", Variable::new(0.0, Some([0.3, 3.0]))), &settings).unwrap();
+    assert!(roots.unwrap().is_empty());
+
+    // as is a jump that only gets smaller as the interval narrows
+    let my_eqn = Expr::parse("x - 0.5 + if(x, 5, 0.5, 1, -1)").unwrap();
+    let roots = all_roots(&my_eqn, ("x", Variable::new(0.0, Some([-0.25, 1.0]))), &settings).unwrap();
+    assert!(roots.unwrap().is_empty());
+
+    let err = all_roots(&my_eqn, ("x", Variable::new(0.0, None)), &settings).err().unwrap();
+    assert!(err.downcast_ref::<DomainRequiredError>().is_some());
+
+    // roots of a badly scaled equation are kept, even though it can't reach the tolerance
+    let my_eqn = Expr::parse("1e8*(x^2 - 3)").unwrap();
+    let roots = all_roots(&my_eqn, ("x", Variable::new(0.0, Some([-10.0, 10.0]))), &settings).unwrap();
+    assert!(roots.residual > settings.tolerance);
+
+    let roots = roots.unwrap();
+    assert_eq!(roots.len(), 2);
+    assert_thou!(roots[0], -3.0_f64.sqrt());
+    assert_thou!(roots[1], 3.0_f64.sqrt());
+
+    let my_code = "keep x on [0, 10]\nroot largest for x\n1e8*(x^2 - 3) = 0";
+    let (soln, log) = solve(my_code, &SolverConfig::default()).unwrap();
+    assert_eq!(log[0].method, Method::AllRoots);
+    assert_thou!(soln["x"].as_f64(), 3.0_f64.sqrt());
+}
+
+#[test]
+fn test_solver_root_choice() {
+    let my_code = |choice: &str| format!(r#"
+        keep x on [-10, 10]
+        root {choice} for x
+        a = 6
+        x^3 - a*x^2 + 11*x - 6 = 0
+    "#);
+
+    let (soln, log) = solve(&my_code("smallest"), &SolverConfig::default()).unwrap();
+    assert_thou!(soln["x"].as_f64(), 1.0);
+    assert_eq!(log[1].method, Method::AllRoots);
+
+    let (soln, _) = solve(&my_code("largest"), &SolverConfig::default()).unwrap();
+    assert_thou!(soln["x"].as_f64(), 3.0);
+
+    let (soln, _) = solve(&my_code("nearest 1.8"), &SolverConfig::default()).unwrap();
+    assert_thou!(soln["x"].as_f64(), 2.0);
+
+    // no root can be chosen without a domain to search
+    assert!(solve("root largest for x\n x^2 = 4", &SolverConfig::default()).is_err());
+}
+
+#[test]
+fn test_solver_sparse() {
+    // a ring of 80 coupled equations, which is solved as one block
+    let n = 80;
+    let my_code: String = (0..n)
+        .map(|i| format!("x{i} + 0.1*x{}^2 = 1\n", (i + 1) % n))
+        .collect();
+
+    let mut my_sys = Nexsys::new(&my_code, SolverConfig::default()).unwrap();
+    assert!(my_sys.settings().sparse_threshold < n);
+
+    let (sparse, log) = my_sys.solve().unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].targets.len(), n);
+
+    my_sys.configure(Settings { sparse_threshold: n, ..Settings::default() });
+    let (dense, _) = my_sys.solve().unwrap();
+
+    let x = (1.4_f64.sqrt() - 1.0) / 0.2;
+    for i in 0..n {
+        let v = format!("x{i}");
+        assert_thou!(sparse[&v].as_f64(), x);
+        assert_thou!(dense[&v].as_f64(), x);
+    }
+}
+#[test]
+fn test_solver_broyden() {
+    let my_code: String = (0..20)
+        .map(|i| format!("x{i} + 0.1*x{}^2 = 1\n", (i + 1) % 20))
+        .collect();
+    let x = (1.4_f64.sqrt() - 1.0) / 0.2;
+
+    for update in [JacobianUpdate::GoodBroyden, JacobianUpdate::BadBroyden] {
+        let mut my_sys = Nexsys::new(&my_code, SolverConfig::default()).unwrap();
+        my_sys.configure(Settings { update, ..Settings::default() });
+
+        let (soln, log) = my_sys.solve().unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].method, Method::Broyden);
+        assert!(log[0].converged);
+
+        for i in 0..20 {
+            assert_thou!(soln[&format!("x{i}")].as_f64(), x);
+        }
+    }
+}
+
+#[test]
+fn test_solver_homotopy() {
+    let my_code = "
+    exp(x) - y = 1000
+    x + y = 10
+    ";
+
+    // full newton steps overshoot badly, and don't recover in 100 iterations
+    let mut my_sys = Nexsys::new(my_code, SolverConfig::builder().tolerance(1e-10).max_iterations(100).build()).unwrap();
+    my_sys.configure(Settings { line_search: false, ..Settings::new(1e-10, 100) });
+
+    let (soln, log) = my_sys.solve().unwrap();
+    assert_eq!(log.last().unwrap().method, Method::Homotopy);
+    assert!(log.last().unwrap().converged);
+
+    assert_thou!(soln["x"].as_f64(), 6.911);
+    assert_thou!(soln["y"].as_f64(), 3.089);
+}
+
+#[test]
+fn test_arc_length() {
+    // a pitchfork: x = 0 for every p, crossed by x^2 = p at p = 0
+    let my_sys = vec![Expr::parse("x^3 - p*x").unwrap()];
+    let start = HashMap::from([
+        ("x", Variable::new(0.0, None)),
+        ("p", Variable::new(-1.0, None))
+    ]);
+
+    let branch = arc_length(&my_sys, start.clone(), "p", [-1.0, 1.0], 0.15, &Settings::default()).unwrap();
+    let p = branch.table.column("p").unwrap();
+
+    assert_eq!(branch.table.columns[0], "p");
+    assert_eq!(branch.points.len(), p.len());
+    assert_thou!(*p.last().unwrap(), 1.0);
+    assert!(branch.table.column("x").unwrap().iter().all(|i| i.abs() < 1e-6));
+
+    let found = branch.find(Point::Bifurcation);
+    assert_eq!(found.len(), 1);
+    assert!(p[found[0] - 1] < 0.0 && p[found[0]] > 0.0);
+    assert!(branch.find(Point::TurningPoint).is_empty());
+
+    // the parameter must be the only free variable
+    let too_few = vec![Expr::parse("x^3 - p*x - q").unwrap()];
+    let mut start = start;
+    start.insert("q", Variable::new(0.0, None));
+    assert!(arc_length(&too_few, start, "p", [-1.0, 1.0], 0.1, &Settings::default()).is_err());
+}
+
+#[test]
+fn test_solver_continuation() {
+    let my_code = "
+    x^2 + p = 1
+    y = 2*x
+    ";
+
+    let mut my_sys = Nexsys::new(my_code, SolverConfig::default()).unwrap();
+    let branch = my_sys.continuation("p", [0.0, 2.0], 0.1, &["y"]).unwrap();
+
+    assert_eq!(branch.table.columns, vec!["p", "y"]);
+
+    // the branch turns back at p = 1 and returns to p = 0 with the other root
+    let p = branch.table.column("p").unwrap();
+    let y = branch.table.column("y").unwrap();
+    assert_thou!(y[0], 2.0);
+    assert_thou!(*p.last().unwrap(), 0.0);
+    assert_thou!(*y.last().unwrap(), -2.0);
+    assert!(p.iter().all(|i| *i <= 1.0 + 1e-9));
+
+    let turns = branch.find(Point::TurningPoint);
+    assert_eq!(turns.len(), 1);
+    assert!(p[turns[0]] > 0.9);
+
+    // the parameter isn't left behind as an edit
+    assert!(my_sys.solve().is_err());
+}
+
+#[test]
+fn test_multistart() {
+    // a cubic and a line cross three times
+    let my_sys = vec![
+        Expr::parse("y - x^3 + x").unwrap(),
+        Expr::parse("y - 0.5*x").unwrap()
+    ];
+    let guess = HashMap::from([
+        ("x", Variable::new(1.0, Some([-3.0, 3.0]))),
+        ("y", Variable::new(1.0, Some([-3.0, 3.0])))
+    ]);
+
+    for sampling in [Sampling::Random, Sampling::LatinHypercube] {
+        let options = Multistart { starts: 10, sampling, seed: 7 };
+        let found = multistart(&my_sys, guess.clone(), &options, &Settings::default()).unwrap();
+        assert!(found.converged());
+
+        let mut x: Vec<f64> = found.unwrap().iter().map(|i| i["x"].as_f64()).collect();
+        x.sort_by(f64::total_cmp);
+
+        assert_eq!(x.len(), 3);
+        assert_thou!(x[0], -(1.5_f64.sqrt()));
+        assert_thou!(x[1], 0.0);
+        assert_thou!(x[2], 1.5_f64.sqrt());
+    }
+
+    // samples can't be drawn without domains
+    let unbounded = HashMap::from([
+        ("x", Variable::new(1.0, None)),
+        ("y", Variable::new(1.0, Some([-3.0, 3.0])))
+    ]);
+    let res = multistart(&my_sys, unbounded, &Multistart::default(), &Settings::default());
+    assert!(res.is_err_and(|e| e.is::<DomainRequiredError>()));
+}
+
+#[test]
+fn test_solver_multistart() {
+    let my_code = "
+    keep x on [-5, 5]
+    keep y on [-5, 5]
+    x^2 + y^2 = 4
+    y = x
+    ";
+
+    let mut my_sys = nexsys::build(my_code, &SolverConfig::default()).unwrap();
+    my_sys.configure(Settings { multistart: Some(Multistart::default()), ..Settings::default() });
+
+    let (soln, log) = my_sys.solve().unwrap();
+    let step = log.last().unwrap();
+
+    assert_eq!(step.method, Method::Multistart);
+    assert_eq!(step.solutions.len(), 2);
+    assert!(step.solutions.iter().any(|i| i["x"] < 0.0));
+
+    // the solution found from the guess is used
+    assert_thou!(soln["x"].as_f64(), 2.0_f64.sqrt());
+    assert!(step.to_string().contains("2 solutions found"));
+}
+
+#[test]
+fn test_bounded_newton() {
+    // the solution (x = 2, y = 1) is outside of the domain of `y`
+    let my_sys = vec![
+        Expr::parse("x + y - 3").unwrap(),
+        Expr::parse("x - y - 1").unwrap()
+    ];
+    let guess = HashMap::from([
+        ("x", Variable::new(0.0, None)),
+        ("y", Variable::new(0.0, Some([0.0, 0.5])))
+    ]);
+
+    let ans = mv_newton_raphson(&my_sys, guess, &Settings::new(1e-10, 20)).unwrap();
+    assert!(!ans.converged());
+
+    let ans = ans.unwrap();
+    assert!(ans["y"].at_bound());
+    assert!(!ans["x"].at_bound());
+    assert_thou!(ans["y"].as_f64(), 0.5);
+    assert_thou!(ans["x"].as_f64(), 2.0);
+
+    // steps that would cross a bound stop on it instead
+    let ans = newton_raphson(&Expr::parse("x^2 - 4").unwrap(), ("x", Variable::new(3.0, Some([2.0, 5.0]))), &Settings::default()).unwrap();
+    assert!(ans.converged());
+    assert!(ans.unwrap().1.at_bound());
+}
+
+#[test]
+fn test_solver_at_bound() {
+    let my_code = "
+    keep x on [2, 5]
+    guess 3 for x
+    x^2 = 4
+    y = x + 1
+    ";
+
+    let (soln, log) = solve(my_code, &SolverConfig::default()).unwrap();
+
+    assert_thou!(soln["x"].as_f64(), 2.0);
+    assert_eq!(log[0].targets, vec!["x"]);
+    assert_eq!(log[0].at_bound, vec!["x"]);
+    assert!(log[0].to_string().contains("on a domain bound: x"));
+    assert!(log[1].at_bound.is_empty());
+}
+
+#[test]
+fn test_scaling() {
+    // the same system with the pressure `p` in Pa and in kPa
+    let pa = vec![
+        Expr::parse("p * a - 3.5e9").unwrap(),
+        Expr::parse("p - 7e9 * a").unwrap()
+    ];
+    let kpa = vec![
+        Expr::parse("p * a - 3.5e6").unwrap(),
+        Expr::parse("p - 7e6 * a").unwrap()
+    ];
+    let guess = |p: f64| HashMap::from([
+        ("p", Variable::new(p, None)),
+        ("a", Variable::new(1.0, None))
+    ]);
+
+    // rounding error in the unscaled residuals is larger than the tolerance
+    let mut settings = Settings::new(1e-10, 50);
+    settings.scaling = false;
+    assert!(!mv_newton_raphson(&pa, guess(1e9), &settings).unwrap().converged());
+
+    settings.scaling = true;
+    let ans_pa = mv_newton_raphson(&pa, guess(1e9), &settings).unwrap();
+    let ans_kpa = mv_newton_raphson(&kpa, guess(1e6), &settings).unwrap();
+    assert!(ans_pa.converged());
+    assert!(ans_kpa.converged());
+
+    let (ans_pa, ans_kpa) = (ans_pa.unwrap(), ans_kpa.unwrap());
+    assert_thou!(ans_pa["a"].as_f64(), 0.5_f64.sqrt());
+    assert_thou!(ans_kpa["a"].as_f64(), 0.5_f64.sqrt());
+    assert_thou!(ans_pa["p"].as_f64() / 1e9, ans_kpa["p"].as_f64() / 1e6);
+}
+
+#[test]
+fn test_solver_typical() {
+    let my_code = "
+    typical 1e9 for p
+    guess 1e9 for p
+    p * a = 3.5e9
+    p = 7e9 * a
+    ";
+    let ans = solve(my_code, &SolverConfig::builder().scaling(true).build()).unwrap();
+    assert_thou!(ans.0["a"].as_f64(), 0.5_f64.sqrt());
+    assert_thou!(ans.0["p"].as_f64() / 1e9, 4.9497);
+}
+
+#[test]
+fn test_convergence_criteria() {
+    let my_sys = vec![
+        Expr::parse("p * a - 3.5e9").unwrap(),
+        Expr::parse("p - 7e9 * a").unwrap()
+    ];
+    let guess = || HashMap::from([
+        ("p", Variable::new(1e9, None)),
+        ("a", Variable::new(1.0, None))
+    ]);
+    let mut settings = Settings::new(1e-10, 50);
+    settings.scaling = false;
+
+    // equations with their own tolerance are measured in their own units
+    settings.convergence = Convergence { equations: vec![Some(1e-3), Some(1e-3)], ..Convergence::default() };
+    let ans = mv_newton_raphson(&my_sys, guess(), &settings).unwrap();
+    assert!(ans.converged());
+    let criteria = ans.criteria.unwrap();
+    assert!(criteria.equations.iter().all(|i| *i <= 1e-3));
+    assert_eq!(criteria.residual, 0.0);
+
+    // or the solve can stop once the steps are small enough
+    settings.convergence = Convergence { norm: Norm::Infinity, step: Some(1e-6), ..Convergence::default() };
+    let ans = mv_newton_raphson(&my_sys, guess(), &settings).unwrap();
+    assert!(ans.converged());
+    assert!(ans.criteria.as_ref().unwrap().step <= 1e-6);
+    assert_thou!(ans.unwrap()["a"].as_f64(), 0.5_f64.sqrt());
+
+    // or once the residuals have dropped by a large enough factor
+    settings.convergence = Convergence { norm: Norm::Two, relative: Some(1e-6), ..Convergence::default() };
+    let ans = newton_raphson(&Expr::parse("1e9 * (x^2 - 2)").unwrap(), ("x", Variable::new(1.0, None)), &settings).unwrap();
+    assert!(ans.converged());
+    assert!(ans.criteria.as_ref().unwrap().relative <= 1e-6);
+    assert_thou!(ans.unwrap().1.as_f64(), 2.0_f64.sqrt());
+}
+
+#[test]
+fn test_step_criteria_pinned() {
+    // the root is outside of the domain of `x`, so `x` is pinned on its bound and the steps
+    // taken vanish, but the full newton steps (and the residuals) stay large
+    let settings = Settings {
+        max_iterations: 30,
+        convergence: Convergence { step: Some(1e-6), ..Convergence::default() },
+        ..Settings::default()
+    };
+
+    let ans = newton_raphson(&Expr::parse("x^2 - 4").unwrap(), ("x", Variable::new(0.5, Some([0.0, 1.0]))), &settings).unwrap();
+    assert!(!ans.converged());
+    assert_eq!(ans.unwrap().1.as_f64(), 1.0);
+
+    let my_sys = vec![
+        Expr::parse("x^2 - 4").unwrap(),
+        Expr::parse("y - x").unwrap()
+    ];
+    let guess = HashMap::from([
+        ("x", Variable::new(0.5, Some([0.0, 1.0]))),
+        ("y", Variable::new(0.5, None))
+    ]);
+    let ans = mv_newton_raphson(&my_sys, guess, &settings).unwrap();
+    assert!(!ans.converged());
+    assert!(ans.residual > 1.0);
+
+    // the same holds for steps shortened by the line search: the residual of `x^2 + 1` 
+    // can't reach zero, but its steps are only shortened while it doesn't grow
+    let ans = newton_raphson(&Expr::parse("x^2 + 1").unwrap(), ("x", Variable::new(0.1, None)), &settings).unwrap();
+    assert!(!ans.converged());
+
+    // the solver reports the block as not converged
+    let config = SolverConfig::builder().convergence(settings.convergence.clone()).fallbacks(vec![]).allow_nonconvergence(true).build();
+    let (soln, log) = solve("keep x on [0, 1]\nkeep y on [0, 1]\nx^2 + y = 4\ny - x = 0", &config).unwrap();
+    assert!(!log.last().unwrap().converged);
+    assert!(soln["x"].as_f64() <= 1.0);
+}
+
+#[test]
+fn test_solver_convergence_criteria() {
+    let my_code = "
+    norm inf
+    tolerance 1e-3 for equation 1
+    tolerance 1e-3 for equation 2
+    p * a = 3.5e9
+    p = 7e9 * a
+    ";
+    let (soln, log) = solve(my_code, &SolverConfig::default()).unwrap();
+    assert_thou!(soln["a"].as_f64(), 0.5_f64.sqrt());
+
+    let criteria = log.last().unwrap().criteria.clone().unwrap();
+    assert_eq!(criteria.equations.len(), 2);
+    assert!(criteria.equations.iter().all(|i| *i <= 1e-3));
+}
+
+#[test]
+fn test_solver_config() {
+    let my_code = "
+    exp(x) - y = 1000
+    x + y = 10
+    ";
+
+    // without fallbacks, newton's method is the only attempt
+    let config = SolverConfig::builder()
+        .line_search(false)
+        .max_iterations(20)
+        .fallbacks(vec![])
+        .allow_nonconvergence(true)
+        .build();
+    let (_, log) = solve(my_code, &config).unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].method, Method::MvNewton);
+    assert!(!log[0].converged);
+
+    // options in the code take precedence
+    let (soln, log) = solve(&format!("options {{\n    max_iterations: 100\n    fallbacks: [homotopy]\n}}\n{my_code}"), &config).unwrap();
+    assert_eq!(log.last().unwrap().method, Method::Homotopy);
+    assert!(log.last().unwrap().converged);
+    assert_thou!(soln["x"].as_f64() + soln["y"].as_f64(), 10.0);
+
+    // the default guess is used for variables without one
+    let config = SolverConfig::builder().default_guess(-2.0).build();
+    let (soln, _) = solve("x^2 = 4", &config).unwrap();
+    assert_thou!(soln["x"].as_f64(), -2.0);
+
+    // configs can be saved and read back
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(serde_json::from_str::<SolverConfig>(&json).unwrap(), config);
+}
+
+#[derive(Default)]
+struct Recorder {
+    iterations: Mutex<Vec<(Method, usize)>>,
+    blocks: Mutex<Vec<Vec<String>>>,
+    steps: Mutex<usize>,
+    cancel: Option<CancellationToken>
+}
+impl Observer for Recorder {
+    fn iteration(&self, method: Method, iteration: usize, _residual: f64) {
+        self.iterations.lock().unwrap().push((method, iteration));
+    }
+
+    fn start(&self, _equations: &[String], targets: &[String]) {
+        self.blocks.lock().unwrap().push(targets.to_vec());
+    }
+
+    fn step(&self, _step: &nexsys::solver::SolveStep) {
+        *self.steps.lock().unwrap() += 1;
+        if let Some(token) = &self.cancel {
+            token.cancel();
+        }
+    }
+}
+
+#[test]
+fn test_solver_monitor() {
+    let my_code = "
+    a = 4
+    x^2 + y = a + 7
+    x + y^2 = 7
+    ";
+
+    // the observer sees every iteration, block and step
+    let recorder = Arc::new(Recorder::default());
+    let config = SolverConfig::builder().observer(recorder.clone()).build();
+    let (_, log) = solve(my_code, &config).unwrap();
+
+    let iterations = recorder.iterations.lock().unwrap();
+    assert_eq!(iterations.len(), log.iter().map(|i| i.iterations).sum::<usize>());
+    assert!(iterations.iter().all(|(m, _)| *m == Method::MvNewton));
+    assert_eq!(recorder.blocks.lock().unwrap().len(), 2);
+    assert_eq!(*recorder.steps.lock().unwrap(), log.len());
+
+    // cancelling stops the solve, keeping what was solved before
+    let token = CancellationToken::new();
+    let recorder = Arc::new(Recorder { cancel: Some(token.clone()), ..Recorder::default() });
+    let config = SolverConfig::builder().observer(recorder).cancellation(token).build();
+    let err = solve(my_code, &config).unwrap_err();
+    let cancelled = err.downcast_ref::<SolverCancelledError>().unwrap();
+    assert_eq!(cancelled.solution["a"].as_f64(), 4.0);
+    assert!(!cancelled.solution.contains_key("x"));
+
+    // running out of time does the same
+    let config = SolverConfig::builder().time_limit(Duration::ZERO).build();
+    let err = solve(my_code, &config).unwrap_err();
+    let timeout = err.downcast_ref::<SolverTimeoutError>().unwrap();
+    assert_eq!(timeout.limit, Duration::ZERO);
+    assert!(timeout.solution.is_empty());
+
+    // a generous limit doesn't get in the way
+    let config = SolverConfig::builder().time_limit(Duration::from_secs(60)).build();
+    assert!(solve(my_code, &config).is_ok());
+
+    // as does one too long to measure
+    let config = SolverConfig { time_limit: Some(1e300), ..SolverConfig::default() };
+    assert!(solve(my_code, &config).is_ok());
+
+    // limits that aren't a number of seconds are rejected instead of timing out at once
+    let config = SolverConfig { time_limit: Some(f64::NAN), ..SolverConfig::default() };
+    let err = Nexsys::new(my_code, config).unwrap().solve().unwrap_err();
+    assert!(err.downcast_ref::<TimeLimitError>().is_some());
+
+    let err = serde_json::from_str::<SolverConfig>(r#"{"time_limit": -1}"#).unwrap_err();
+    assert!(err.to_string().contains("time limit must be"));
+
+    let err = solve(&format!("options {{\n time_limit: -1\n}}\n{my_code}"), &SolverConfig::default()).unwrap_err();
+    assert!(err.to_string().contains("time limit must be"));
+}
+
+#[test]
+fn test_history() {
+    let my_sys = vec![
+        Expr::parse("x^2 + y - 11").unwrap(),
+        Expr::parse("x + y^2 - 7").unwrap()
+    ];
+    let guess = HashMap::from([
+        ("x", Variable::new(1.0, None)),
+        ("y", Variable::new(1.0, None))
+    ]);
+
+    // off by default
+    let ans = mv_newton_raphson(&my_sys, guess.clone(), &Settings::default()).unwrap();
+    assert!(ans.history.is_empty());
+
+    let settings = Settings { history: true, ..Settings::default() };
+    let ans = mv_newton_raphson(&my_sys, guess, &settings).unwrap();
+    assert_eq!(ans.history.len(), ans.iterations);
+    assert_eq!(ans.history.last().unwrap().residual, ans.residual);
+    assert!(ans.history.iter().all(|i| i.condition.is_finite() && i.step > 0.0));
+    let last = ans.history.last().unwrap().values.clone();
+    let soln = ans.unwrap();
+    assert_eq!(last["x"], soln["x"].as_f64());
+
+    let ans = newton_raphson(&Expr::parse("x^2 - 4").unwrap(), ("x", Variable::new(1.0, None)), &settings).unwrap();
+    assert_eq!(ans.history.len(), ans.iterations);
+    assert_thou!(ans.history.last().unwrap().values["x"], 2.0);
+
+    // the history is kept in the log, and can be turned on from the options block
+    let (_, log) = solve("options {\n    history: true\n}\na = 4\nx^2 + y = a + 7\nx + y^2 = 7", &SolverConfig::default()).unwrap();
+    assert!(log[0].history.is_empty()); // `a` is solved directly
+    assert_eq!(log[1].history.len(), log[1].iterations);
+
+    // the log (and its history) is kept when a block doesn't converge...
+    let config = SolverConfig::builder().history(true).max_iterations(20).fallbacks(vec![]).build();
+    let err = solve("keep x on [0, 1]\nkeep y on [0, 1]\nx^2 + y = 4\ny - x = 0", &config).unwrap_err();
+    let log = &err.downcast_ref::<SolverConvergenceError>().unwrap().log;
+    assert_eq!(log.len(), 1);
+    assert!(!log[0].converged);
+    assert_eq!(log[0].history.len(), log[0].iterations);
+    assert!(!log[0].at_bound.is_empty()); // the failed values are pinned to their domains
+
+    // ...and when the system isn't properly constrained
+    let err = solve("a = 4\nx + y = a", &config).unwrap_err();
+    let log = &err.downcast_ref::<SolverConstraintError>().unwrap().log;
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].targets, vec!["a"]);
+}